@@ -0,0 +1,214 @@
+//! Checks a juror draw of the voter validation from its transcript, returned by the
+//! `get_draw_transcript` view of the contract, against the random seed it was drawn with.
+
+use rand::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Deserializer};
+
+/// Inputs and draws of one round of a voter application.
+#[derive(Debug, Deserialize)]
+pub struct DrawTranscript {
+    pub round: u64,
+    /// Random seed of the block the jurors were drawn in.
+    pub seed: Vec<u8>,
+    /// User ids of the juror applicants before the draw, in the order the draw goes
+    /// through them.
+    #[serde(deserialize_with = "deserialize_u128_strings")]
+    pub candidates: Vec<u128>,
+    /// Draw weights of the candidates, in the same order.
+    #[serde(deserialize_with = "deserialize_u128_strings")]
+    pub weights: Vec<u128>,
+    /// Draws in the order they were made.
+    pub draws: Vec<JurorDraw>,
+}
+
+/// One draw of a juror, the draw number picks the juror whose weight range holds it modulo
+/// the total weight left. Withdrawn jurors leave the draw like drawn ones.
+#[derive(Debug, Deserialize)]
+pub struct JurorDraw {
+    #[serde(deserialize_with = "deserialize_u128_string")]
+    pub draw_number: u128,
+    #[serde(deserialize_with = "deserialize_u128_string")]
+    pub juror_user_id: u128,
+    #[serde(deserialize_with = "deserialize_u128_string")]
+    pub weight: u128,
+    /// The juror had a conflict of interest with the voter and was withdrawn instead.
+    pub withdrawn: bool,
+}
+
+/// The contract serializes u128 as strings, like `U128` of near-sdk.
+fn deserialize_u128_string<'de, D>(deserializer: D) -> Result<u128, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let string: String = Deserialize::deserialize(deserializer)?;
+    string.parse().map_err(serde::de::Error::custom)
+}
+
+fn deserialize_u128_strings<'de, D>(deserializer: D) -> Result<Vec<u128>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strings: Vec<String> = Deserialize::deserialize(deserializer)?;
+    strings
+        .iter()
+        .map(|string| string.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Same random number generator as the contract, seeded with the block random seed.
+/// ChaCha20 gives the same numbers on every platform and version of the crate.
+pub fn get_rng(seed_vec: &[u8]) -> ChaCha20Rng {
+    let mut seed = [0u8; 32];
+    for (i, v) in seed_vec.iter().take(32).enumerate() {
+        seed[i] = *v;
+    }
+    SeedableRng::from_seed(seed)
+}
+
+/// Returns the first `count` draw numbers generated from the seed.
+pub fn draw_numbers(seed: &[u8], count: usize) -> Vec<u128> {
+    let mut rng = get_rng(seed);
+    (0..count).map(|_| rng.gen::<u128>()).collect()
+}
+
+/// Checks that the draw numbers of the transcript are the ones generated from its seed, and
+/// that each number picks the recorded juror and weight from the candidates left, the same
+/// way the sum tree draw of the contract does.
+pub fn verify(transcript: &DrawTranscript) -> Result<(), String> {
+    if transcript.candidates.len() != transcript.weights.len() {
+        return Err(format!(
+            "{} candidates have {} weights",
+            transcript.candidates.len(),
+            transcript.weights.len()
+        ));
+    }
+    let mut remaining: Vec<(u128, u128)> = transcript
+        .candidates
+        .iter()
+        .cloned()
+        .zip(transcript.weights.iter().cloned())
+        .collect();
+    let numbers = draw_numbers(&transcript.seed, transcript.draws.len());
+    for (index, (draw, number)) in transcript.draws.iter().zip(numbers).enumerate() {
+        if draw.draw_number != number {
+            return Err(format!(
+                "Draw {} has the number {} but the seed generates {}",
+                index, draw.draw_number, number
+            ));
+        }
+        let (juror_user_id, weight) = match pick(&remaining, number) {
+            Some(position) => remaining.remove(position),
+            None => return Err(format!("No candidate is left for draw {}", index)),
+        };
+        if draw.juror_user_id != juror_user_id || draw.weight != weight {
+            return Err(format!(
+                "Draw {} records juror {} with the weight {} but picks juror {} with the weight {}",
+                index, draw.juror_user_id, draw.weight, juror_user_id, weight
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the position of the candidate whose weight range holds the draw number modulo
+/// the total weight of the candidates.
+fn pick(candidates: &[(u128, u128)], draw_number: u128) -> Option<usize> {
+    let total_weight: u128 = candidates.iter().map(|(_, weight)| weight).sum();
+    if total_weight == 0 {
+        return None;
+    }
+    let mut current_draw_number = draw_number % total_weight;
+    for (position, (_, weight)) in candidates.iter().enumerate() {
+        if current_draw_number < *weight {
+            return Some(position);
+        }
+        current_draw_number -= weight;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws two of the candidates 3, 5 and 8 with the weights 40, 60 and 100.
+    fn transcript_json(seed: &[u8], numbers: &[u128], jurors: &[u128]) -> String {
+        format!(
+            r#"{{"round":0,"seed":{:?},"candidates":["3","5","8"],"weights":["40","60","100"],"draws":[{{"draw_number":"{}","juror_user_id":"{}","weight":"{}","withdrawn":false}},{{"draw_number":"{}","juror_user_id":"{}","weight":"{}","withdrawn":true}}]}}"#,
+            seed,
+            numbers[0],
+            jurors[0],
+            juror_weight(jurors[0]),
+            numbers[1],
+            jurors[1],
+            juror_weight(jurors[1])
+        )
+    }
+
+    fn juror_weight(juror_user_id: u128) -> u128 {
+        match juror_user_id {
+            3 => 40,
+            5 => 60,
+            _ => 100,
+        }
+    }
+
+    /// Jurors picked by the draw numbers, the picked juror leaves the draw.
+    fn drawn_jurors(numbers: &[u128]) -> Vec<u128> {
+        let mut candidates = vec![(3, 40), (5, 60), (8, 100)];
+        numbers
+            .iter()
+            .map(|number| {
+                let position = pick(&candidates, *number).unwrap();
+                candidates.remove(position).0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draw_numbers_are_reproducible() {
+        let seed = vec![7; 32];
+        assert_eq!(draw_numbers(&seed, 4), draw_numbers(&seed, 4));
+        assert_eq!(draw_numbers(&seed, 4)[..2], draw_numbers(&seed, 2)[..]);
+        assert_ne!(draw_numbers(&seed, 1), draw_numbers(&[8; 32], 1));
+    }
+
+    #[test]
+    fn pick_follows_the_weight_ranges() {
+        let candidates = [(3, 40), (5, 60), (8, 100)];
+        assert_eq!(pick(&candidates, 0), Some(0));
+        assert_eq!(pick(&candidates, 39), Some(0));
+        assert_eq!(pick(&candidates, 40), Some(1));
+        assert_eq!(pick(&candidates, 100), Some(2));
+        assert_eq!(pick(&candidates, 200), Some(0));
+        assert_eq!(pick(&[(3, 0)], 7), None);
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_transcript() {
+        let seed = vec![1, 2, 3];
+        let numbers = draw_numbers(&seed, 2);
+        let jurors = drawn_jurors(&numbers);
+        let transcript: DrawTranscript =
+            serde_json::from_str(&transcript_json(&seed, &numbers, &jurors)).unwrap();
+        assert_eq!(verify(&transcript), Ok(()));
+        let swapped: DrawTranscript =
+            serde_json::from_str(&transcript_json(&seed, &[numbers[1], numbers[0]], &jurors))
+                .unwrap();
+        assert!(verify(&swapped).is_err());
+        // The third candidate is recorded instead of the drawn juror
+        let undrawn = [3, 5, 8]
+            .iter()
+            .cloned()
+            .find(|juror| !jurors.contains(juror))
+            .unwrap();
+        let replaced: DrawTranscript =
+            serde_json::from_str(&transcript_json(&seed, &numbers, &[undrawn, jurors[1]])).unwrap();
+        assert!(verify(&replaced).is_err());
+        let mut missing_weight = transcript;
+        missing_weight.weights.pop();
+        assert!(verify(&missing_weight).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+//! Verifies the juror draw transcripts in the JSON files given as arguments.
+//! Usage: draw-verifier <transcript.json>...
+
+use draw_verifier::{verify, DrawTranscript};
+use std::{env, fs, process};
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: draw-verifier <transcript.json>...");
+        process::exit(2);
+    }
+    let mut failed = false;
+    for path in paths.iter() {
+        let result = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| {
+                serde_json::from_str::<DrawTranscript>(&json).map_err(|err| err.to_string())
+            })
+            .and_then(|transcript| verify(&transcript).map(|_| transcript.round));
+        match result {
+            Ok(round) => println!("{}: draw of round {} is valid", path, round),
+            Err(err) => {
+                failed = true;
+                println!("{}: {}", path, err);
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
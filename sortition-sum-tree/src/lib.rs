@@ -0,0 +1,295 @@
+//! K-ary sum tree of stakes stored in NEAR collections, used to draw ids weighted by stake.
+//!
+//! The tree is a plain struct that a contract embeds in its state, for example one tree per
+//! dispute in a `LookupMap`. Every mutation changes the lengths kept in the struct, so write
+//! the tree back to the contract state after calling `set`.
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{TreeMap, Vector};
+
+/// Every leaf is the stake of an id and every other node is the sum of its children.
+/// Setting a stake and drawing an id weighted by stake are O(k·log n) storage operations.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SortitionSumTree {
+    k: u128,
+    stack: Vector<u128>,
+    nodes: Vector<u128>,
+    ids_to_node_indexes: TreeMap<String, u128>,
+    node_indexes_to_ids: TreeMap<u128, String>,
+}
+
+impl SortitionSumTree {
+    /// Creates an empty tree with `k` children per node, storing its collections under `prefix`.
+    pub fn new(k: u128, prefix: String) -> Self {
+        if k < 2 {
+            panic!("K must be greater than one");
+        }
+        let mut nodes = Vector::new(format!("{}nodes", prefix).into_bytes());
+        nodes.push(&0);
+        SortitionSumTree {
+            k,
+            stack: Vector::new(format!("{}stack", prefix).into_bytes()),
+            nodes,
+            ids_to_node_indexes: TreeMap::new(format!("{}idstonodes", prefix).into_bytes()),
+            node_indexes_to_ids: TreeMap::new(format!("{}nodestoids", prefix).into_bytes()),
+        }
+    }
+
+    /// Sets the stake of `id` to `value`, a value of 0 removes the id from the tree.
+    pub fn set(&mut self, value: u128, id: String) {
+        match self.ids_to_node_indexes.get(&id) {
+            Some(tree_index) => {
+                let current_value = self.nodes.get(tree_index as u64).unwrap();
+                if value == 0 {
+                    self.nodes.replace(tree_index as u64, &0);
+                    self.stack.push(&tree_index);
+                    self.ids_to_node_indexes.remove(&id);
+                    self.node_indexes_to_ids.remove(&tree_index);
+                    self.update_parents(tree_index, false, current_value);
+                } else if value != current_value {
+                    self.nodes.replace(tree_index as u64, &value);
+                    if current_value <= value {
+                        self.update_parents(tree_index, true, value - current_value);
+                    } else {
+                        self.update_parents(tree_index, false, current_value - value);
+                    }
+                }
+            }
+            None => {
+                if value == 0 {
+                    return;
+                }
+                let tree_index = match self.stack.pop() {
+                    // Reuse a vacated leaf
+                    Some(tree_index) => {
+                        self.nodes.replace(tree_index as u64, &value);
+                        tree_index
+                    }
+                    None => {
+                        let tree_index = self.nodes.len() as u128;
+                        self.nodes.push(&value);
+                        // The parent of a first child was a leaf, move it down to become
+                        // the second child and turn the parent into a sum node.
+                        if tree_index != 1 && (tree_index - 1).is_multiple_of(self.k) {
+                            let parent_index = tree_index / self.k;
+                            let parent_id = self.node_indexes_to_ids.get(&parent_index).unwrap();
+                            let new_index = tree_index + 1;
+                            self.nodes
+                                .push(&self.nodes.get(parent_index as u64).unwrap());
+                            self.node_indexes_to_ids.remove(&parent_index);
+                            self.ids_to_node_indexes.insert(&parent_id, &new_index);
+                            self.node_indexes_to_ids.insert(&new_index, &parent_id);
+                        }
+                        tree_index
+                    }
+                };
+                self.ids_to_node_indexes.insert(&id, &tree_index);
+                self.node_indexes_to_ids.insert(&tree_index, &id);
+                self.update_parents(tree_index, true, value);
+            }
+        }
+    }
+
+    fn update_parents(&mut self, tree_index: u128, plus_or_minus: bool, value: u128) {
+        let mut parent_index = tree_index;
+        while parent_index != 0 {
+            parent_index = (parent_index - 1) / self.k;
+            let parent_value = self.nodes.get(parent_index as u64).unwrap();
+            let tree_node_value = if plus_or_minus {
+                parent_value + value
+            } else {
+                parent_value - value
+            };
+            self.nodes.replace(parent_index as u64, &tree_node_value);
+        }
+    }
+
+    /// Returns the id whose stake range contains `draw_number`, taken modulo the total stake.
+    /// Returns None if the tree is empty.
+    pub fn draw(&self, draw_number: u128) -> Option<String> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let mut tree_index = 0;
+        let mut current_draw_number = draw_number % total;
+        while (self.k * tree_index) + 1 < (self.nodes.len() as u128) {
+            for i in 1..self.k + 1 {
+                let node_index = (self.k * tree_index) + i;
+                let node_value = self.nodes.get(node_index as u64).unwrap_or(0);
+                if current_draw_number >= node_value {
+                    current_draw_number -= node_value;
+                } else {
+                    tree_index = node_index;
+                    break;
+                }
+            }
+        }
+        self.node_indexes_to_ids.get(&tree_index)
+    }
+
+    /// Returns the ids and their values in the order `draw` goes through them, so a draw
+    /// number picks the first id whose cumulative value is above it.
+    pub fn to_draw_order_vec(&self) -> Vec<(String, u128)> {
+        let mut leaves = Vec::new();
+        self.push_leaves(0, &mut leaves);
+        leaves
+    }
+
+    fn push_leaves(&self, tree_index: u128, leaves: &mut Vec<(String, u128)>) {
+        let nodes_len = self.nodes.len() as u128;
+        if (self.k * tree_index) + 1 < nodes_len {
+            for i in 1..self.k + 1 {
+                let node_index = (self.k * tree_index) + i;
+                if node_index < nodes_len {
+                    self.push_leaves(node_index, leaves);
+                }
+            }
+        } else if let Some(id) = self.node_indexes_to_ids.get(&tree_index) {
+            leaves.push((id, self.nodes.get(tree_index as u64).unwrap()));
+        }
+    }
+
+    pub fn stake_of(&self, id: &String) -> u128 {
+        match self.ids_to_node_indexes.get(id) {
+            Some(tree_index) => self.nodes.get(tree_index as u64).unwrap(),
+            None => 0,
+        }
+    }
+
+    /// Sum of all the stakes in the tree.
+    pub fn total(&self) -> u128 {
+        self.nodes.get(0).unwrap()
+    }
+
+    /// Number of ids with a stake in the tree.
+    pub fn len(&self) -> u64 {
+        self.ids_to_node_indexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every node of the tree from storage, so the tree is dropped along with its
+    /// collections.
+    pub fn clear(mut self) {
+        self.stack.clear();
+        self.nodes.clear();
+        self.ids_to_node_indexes.clear();
+        self.node_indexes_to_ids.clear();
+    }
+
+    /// Returns all the ids with their stakes, reads every leaf of the tree.
+    pub fn to_vec(&self) -> Vec<(String, u128)> {
+        self.ids_to_node_indexes
+            .iter()
+            .map(|(id, tree_index)| (id, self.nodes.get(tree_index as u64).unwrap()))
+            .collect()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{env, testing_env, VMContext};
+
+    fn get_context() -> VMContext {
+        VMContext {
+            current_account_id: "alice_near".to_string(),
+            signer_account_id: "bob_near".to_string(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id: "carol_near".to_string(),
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            account_balance: 0,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            epoch_height: 0,
+            output_data_receivers: vec![],
+        }
+    }
+
+    // Every draw number below the total stake draws an id, so each id is drawn as many times
+    // as its stake.
+    fn assert_draws_match_stakes(tree: &SortitionSumTree, stakes: &[(u128, u128)]) {
+        let total: u128 = stakes.iter().map(|(_, stake)| stake).sum();
+        assert_eq!(tree.total(), total);
+        let mut draws = std::collections::HashMap::new();
+        for draw_number in 0..total {
+            let id = tree.draw(draw_number).unwrap();
+            *draws.entry(id).or_insert(0) += 1;
+        }
+        for (id, stake) in stakes.iter() {
+            assert_eq!(tree.stake_of(&id.to_string()), *stake);
+            assert_eq!(draws.get(&id.to_string()).cloned().unwrap_or(0), *stake);
+        }
+        assert_draw_order_matches_draws(tree, total);
+    }
+
+    fn assert_draw_order_matches_draws(tree: &SortitionSumTree, total: u128) {
+        // Checks the first and the last draw number of each id
+        let leaves = tree.to_draw_order_vec();
+        let mut cumulative = 0;
+        for (id, stake) in leaves.iter() {
+            assert_eq!(tree.draw(cumulative).as_ref(), Some(id));
+            cumulative += stake;
+            assert_eq!(tree.draw(cumulative - 1).as_ref(), Some(id));
+        }
+        assert_eq!(cumulative, total);
+    }
+
+    #[test]
+    fn sortition_sum_tree_draws_by_stake() {
+        let context = get_context();
+        testing_env!(context);
+        let mut tree = SortitionSumTree::new(3, "testtree".to_owned());
+        assert_eq!(tree.draw(5), None);
+        let mut stakes: Vec<(u128, u128)> = (1..12).map(|id| (id, id * 2)).collect();
+        for (id, stake) in stakes.iter() {
+            tree.set(*stake, id.to_string());
+        }
+        assert_eq!(tree.len(), 11);
+        assert_draws_match_stakes(&tree, &stakes);
+
+        // Remove, update and add back ids, reusing the vacated leaves
+        tree.set(0, "4".to_owned());
+        tree.set(0, "9".to_owned());
+        tree.set(9, "2".to_owned());
+        tree.set(3, "11".to_owned());
+        tree.set(7, "12".to_owned());
+        stakes.retain(|(id, _)| *id != 4 && *id != 9);
+        stakes[1].1 = 9;
+        stakes[8].1 = 3;
+        stakes.push((12, 7));
+        assert_eq!(tree.len(), 10);
+        assert_draws_match_stakes(&tree, &stakes);
+        assert_eq!(tree.stake_of(&"4".to_owned()), 0);
+    }
+
+    #[test]
+    fn sortition_sum_tree_clear_frees_storage() {
+        testing_env!(get_context());
+        let initial_storage = env::storage_usage();
+        let mut tree = SortitionSumTree::new(2, "testtree".to_owned());
+        for id in 1..8u128 {
+            tree.set(id, id.to_string());
+        }
+        tree.set(0, "3".to_owned());
+        tree.clear();
+        assert_eq!(env::storage_usage(), initial_storage);
+    }
+
+    #[test]
+    #[should_panic(expected = "K must be greater than one")]
+    fn sortition_sum_tree_with_k_below_two_fails() {
+        testing_env!(get_context());
+        SortitionSumTree::new(1, "testtree".to_owned());
+    }
+}
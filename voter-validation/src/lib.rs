@@ -2,11 +2,23 @@ pub mod shivarthu;
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use crate::shivarthu::{FungibleToken, STORAGE_PRICE_PER_BYTE};
-    use near_sdk::MockedBlockchain;
+    use crate::shivarthu::events::{Event, FtEvent, FtTransfer};
+    use crate::shivarthu::{
+        AccountV1, ApplicationPhase, FungibleToken, FungibleTokenMetadata, FungibleTokenV1,
+        JurorApplicationStatus, JurorReputation, ValidationConfig, VersionedFungibleToken,
+        VoterRewardCurve, VoterV1, APPEAL_PERIOD, COMMIT_PERIOD, DRAW_PERIOD,
+        EXPERTISE_APPLICATION_ID_START, JUROR_APPLICATION_PERIOD, JUROR_INCENTIVE,
+        RECIPROCAL_JURY_COOLDOWN, REVEAL_PERIOD, STATE_VERSION, STORAGE_PRICE_PER_BYTE,
+    };
+    use near_sdk::borsh::BorshSerialize;
+    use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap};
+    use near_sdk::serde_json;
+    use near_sdk::json_types::U128;
     use near_sdk::{env, AccountId, Balance};
     use near_sdk::{testing_env, VMContext};
+    use near_sdk::{MockedBlockchain, PromiseResult};
     use rand::Rng;
+    use sha3::{Digest, Keccak256};
     use std::panic;
 
     fn rand_vector() -> Vec<u8> {
@@ -26,6 +38,26 @@ mod tests {
         return result;
     }
 
+    fn draw_phase_time() -> u64 {
+        JUROR_APPLICATION_PERIOD
+    }
+
+    fn commit_phase_time() -> u64 {
+        JUROR_APPLICATION_PERIOD + DRAW_PERIOD
+    }
+
+    fn reveal_phase_time() -> u64 {
+        JUROR_APPLICATION_PERIOD + DRAW_PERIOD + COMMIT_PERIOD
+    }
+
+    fn appeal_phase_time() -> u64 {
+        JUROR_APPLICATION_PERIOD + DRAW_PERIOD + COMMIT_PERIOD + REVEAL_PERIOD
+    }
+
+    fn settlement_phase_time() -> u64 {
+        appeal_phase_time() + APPEAL_PERIOD
+    }
+
     fn alice() -> AccountId {
         "alice.near".to_string()
     }
@@ -332,6 +364,19 @@ mod tests {
         );
     }
 
+    /// Registers `account_id` with a storage balance enough for the voter validation tests.
+    fn storage_deposit_for_test(
+        account_id: AccountId,
+        contract: &mut FungibleToken,
+        context: &VMContext,
+    ) {
+        let mut deposit_context = context.clone();
+        deposit_context.attached_deposit = 100_000 * STORAGE_PRICE_PER_BYTE;
+        testing_env!(deposit_context);
+        contract.storage_deposit(Some(account_id), None);
+        testing_env!(context.clone());
+    }
+
     #[test]
     fn test_voter_addition() {
         let mut context = get_context(carol());
@@ -339,6 +384,7 @@ mod tests {
         let total_supply = 1_000_000_000_000_000u128;
         let mut contract = FungibleToken::new(carol(), total_supply.into());
         context.storage_usage = env::storage_usage();
+        storage_deposit_for_test(bob(), &mut contract, &context);
         contract.create_voter_profile("c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned());
         let voter_id = contract.get_user_id(&bob());
         assert_eq!(voter_id, 1);
@@ -349,12 +395,33 @@ mod tests {
         );
     }
 
+    fn test_validation_config() -> ValidationConfig {
+        ValidationConfig {
+            jury_size_percent: 100,
+            min_jurors: 3,
+            voter_reward_curve: VoterRewardCurve::Quadratic,
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        }
+    }
+
     fn voter_stake() -> (FungibleToken, VMContext) {
+        voter_stake_with_config(test_validation_config())
+    }
+
+    fn voter_stake_with_config(validation_config: ValidationConfig) -> (FungibleToken, VMContext) {
         let mut context = get_context(carol());
         testing_env!(context.clone());
         let total_supply = 1_000_000_000_000_000u128;
-        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        let mut contract = FungibleToken::new_with_config(
+            carol(),
+            total_supply.into(),
+            FungibleTokenMetadata::default(),
+            validation_config,
+        );
         context.storage_usage = env::storage_usage();
+        storage_deposit_for_test(bob(), &mut contract, &context);
         contract.create_voter_profile("c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned());
         let voter_id = contract.get_user_id(&bob());
         assert_eq!(voter_id, 1);
@@ -387,10 +454,11 @@ mod tests {
         contract.create_voter_stake(50);
         // println!("{}", contract.get_balance(bob()).0);
         assert_eq!(contract.get_balance(bob()).0, transfer_amount - 50);
+        assert_eq!(contract.get_locked_balance(bob()).0, 50);
         // let totalsupply = contract.get_total_supply();
         // println!("{}", totalsupply.0);
-        assert_eq!(contract.get_total_supply().0, intialtotalsupply - 50);
-        let stake = contract.get_voter_stake(voter_id);
+        assert_eq!(contract.get_total_supply().0, intialtotalsupply);
+        let stake = contract.get_voter_stake(U128(voter_id));
         // println!(">>>>>{}<<<<<<<", stake);
         assert_eq!(stake, 50);
 
@@ -400,7 +468,7 @@ mod tests {
         testing_env!(context.clone());
         contract.transfer(user2(), 150.into());
         context.is_view = false;
-        testing_env!(context.clone());
+        storage_deposit_for_test(user2(), &mut contract, &context);
         contract.create_voter_profile("user2profile".to_owned());
         let juror_id = contract.get_user_id(&user2());
         // println!(">>>>>>{}<<<<<<<", juror_id);
@@ -410,8 +478,10 @@ mod tests {
 
         contract.apply_jurors(bob(), 51);
         let voter_id = contract.get_user_id(&bob());
-        assert_eq!(contract.get_total_supply().0, intialtotalsupply - 50 - 51);
-        let data = contract.get_juror_stakes(voter_id, juror_id);
+        assert_eq!(contract.get_balance(user2()).0, 150 - 51);
+        assert_eq!(contract.get_locked_balance(user2()).0, 51);
+        assert_eq!(contract.get_total_supply().0, intialtotalsupply);
+        let data = contract.get_juror_stakes(U128(voter_id), juror_id);
         assert_eq!(data, 51);
         // println!(">>>>>>>>{:?}<<<<<<<<<", all_data.get(&1));
 
@@ -440,17 +510,20 @@ mod tests {
     fn same_juror_different_voter() {
         let (mut contract, mut context) = voter_stake();
         context.signer_account_id = user3();
-        testing_env!(context.clone());
+        storage_deposit_for_test(user3(), &mut contract, &context);
         contract.create_voter_profile("user3profile".to_owned());
         let voter_id = contract.get_user_id(&user3());
         // println!(">>>>>>{}<<<<<<<", voter_id);
         assert_eq!(voter_id, 3);
+        contract.transfer(user3(), 150.into());
+        contract.create_voter_stake(50);
         context.signer_account_id = user2();
         testing_env!(context.clone());
         let intialtotalsupply = contract.get_total_supply().0;
         // println!("Initial Supply>>>>{}<<<<<<",intialtotalsupply);
         contract.apply_jurors(user3(), 53);
-        assert_eq!(contract.get_total_supply().0, intialtotalsupply - 53);
+        assert_eq!(contract.get_total_supply().0, intialtotalsupply);
+        assert_eq!(contract.get_locked_balance(user2()).0, 51 + 53);
     }
 
     fn create_a_user(
@@ -460,7 +533,7 @@ mod tests {
         mut context: VMContext,
     ) -> (FungibleToken, VMContext) {
         context.signer_account_id = username.clone();
-        testing_env!(context.clone());
+        storage_deposit_for_test(username.clone(), &mut contract, &context);
         contract.create_voter_profile(profilehash);
         context.attached_deposit = 1000 * STORAGE_PRICE_PER_BYTE;
         testing_env!(context.clone());
@@ -481,9 +554,25 @@ mod tests {
         (contract, context)
     }
 
-    #[test]
-    fn draw_juror() {
-        let (contract, context) = voter_stake();
+    fn draw_jurors_for_test() -> (FungibleToken, VMContext) {
+        draw_jurors_with_config_for_test(test_validation_config())
+    }
+
+    fn draw_jurors_with_config_for_test(
+        validation_config: ValidationConfig,
+    ) -> (FungibleToken, VMContext) {
+        let (mut contract, mut context) = juror_applications_for_test(validation_config);
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        (contract, context)
+    }
+
+    fn juror_applications_for_test(
+        validation_config: ValidationConfig,
+    ) -> (FungibleToken, VMContext) {
+        let (contract, context) = voter_stake_with_config(validation_config);
         // contract.draw_jurors(bob());
 
         // Add 5 jurors for bob()
@@ -547,16 +636,12 @@ mod tests {
             contract,
             context.clone(),
         );
-        let (mut contract, mut context) = apply_jurors_for_test_function(
-            bob(),
-            "juror5".to_owned(),
-            20,
-            contract,
-            context.clone(),
-        );
-        context.random_seed = rand_vector();
-        testing_env!(context.clone());
-        contract.draw_jurors(bob());
+        apply_jurors_for_test_function(bob(), "juror5".to_owned(), 20, contract, context.clone())
+    }
+
+    #[test]
+    fn draw_juror() {
+        let (contract, _context) = draw_jurors_for_test();
         let bob_id = contract.get_user_id(&bob());
         let jurylist = contract.get_selected_jurors(bob_id);
         let four = jurylist.contains(&4);
@@ -575,4 +660,2104 @@ mod tests {
         println!("{:?}", ten);
 
     }
+
+    fn vote_commit(juror: &str, vote: &str) -> String {
+        let mut hasher = Keccak256::new();
+        hasher.update(juror.as_bytes());
+        hasher.update(vote.as_bytes());
+        let result = hasher.finalize();
+        format!("{:x}", result)
+    }
+
+    #[test]
+    fn commit_and_reveal_juror_vote() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "1juror1secret"));
+        context.signer_account_id = "juror2".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror2", "0juror2secret"));
+
+        context.block_timestamp = reveal_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+        context.signer_account_id = "juror2".to_owned();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "0juror2secret".to_owned());
+
+        let bob_id = contract.get_user_id(&bob());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        let juror2_id = contract.get_user_id(&"juror2".to_owned());
+        let juror3_id = contract.get_user_id(&"juror3".to_owned());
+        assert_eq!(contract.get_juror_vote(U128(bob_id), juror1_id), Some(true));
+        assert_eq!(
+            contract.get_juror_vote(U128(bob_id), juror2_id),
+            Some(false)
+        );
+        assert_eq!(contract.get_juror_vote(U128(bob_id), juror3_id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Vote commit is already done")]
+    fn commit_juror_vote_twice_fails() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "1juror1secret"));
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "0juror1secret"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Vote hash doesn't match the vote commit")]
+    fn reveal_juror_vote_copied_from_another_juror_fails() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        let juror1_commit = vote_commit("juror1", "1juror1secret");
+        contract.commit_juror_vote(bob(), juror1_commit.clone());
+        // juror2 copies the commit of juror1 and replays its reveal
+        context.signer_account_id = "juror2".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), juror1_commit);
+        context.block_timestamp = reveal_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+        context.signer_account_id = "juror2".to_owned();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not a selected juror for the voter")]
+    fn commit_juror_vote_by_unselected_juror_fails() {
+        let (contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        let (mut contract, _context) = create_a_user(
+            "juror6".to_owned(),
+            "juror6######XXXXX".to_owned(),
+            contract,
+            context,
+        );
+        contract.commit_juror_vote(bob(), vote_commit("juror6", "1juror6secret"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Jurors are not drawn for the voter")]
+    fn commit_juror_vote_before_draw_fails() {
+        let (mut contract, mut context) = voter_stake();
+        context.block_timestamp = commit_phase_time();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit(&user2(), "1user2secret"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Vote hash doesn't match the vote commit")]
+    fn reveal_juror_vote_with_wrong_vote_fails() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "1juror1secret"));
+        context.block_timestamp = reveal_phase_time();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "0juror1secret".to_owned());
+    }
+
+    #[test]
+    #[should_panic(expected = "The vote was already revealed")]
+    fn reveal_juror_vote_twice_fails() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "1juror1secret"));
+        context.block_timestamp = reveal_phase_time();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+    }
+
+    fn juror_votes_for_test() -> (FungibleToken, VMContext) {
+        let votes = [
+            ("juror1", "1juror1secret"),
+            ("juror2", "1juror2secret"),
+            ("juror3", "1juror3secret"),
+            ("juror4", "0juror4secret"),
+            ("juror5", "0juror5secret"),
+        ];
+        // juror5 doesn't reveal the vote
+        cast_juror_votes_for_test(&votes, 4)
+    }
+
+    fn cast_juror_votes_for_test(
+        votes: &[(&str, &str)],
+        revealed: usize,
+    ) -> (FungibleToken, VMContext) {
+        cast_juror_votes_with_config_for_test(test_validation_config(), votes, revealed)
+    }
+
+    fn cast_juror_votes_with_config_for_test(
+        validation_config: ValidationConfig,
+        votes: &[(&str, &str)],
+        revealed: usize,
+    ) -> (FungibleToken, VMContext) {
+        let (mut contract, mut context) = draw_jurors_with_config_for_test(validation_config);
+        context.block_timestamp = commit_phase_time();
+        for (juror, vote) in votes.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.commit_juror_vote(bob(), vote_commit(juror, vote));
+        }
+        context.block_timestamp = reveal_phase_time();
+        for (juror, vote) in votes[0..revealed].iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.reveal_juror_vote(bob(), vote.to_string());
+        }
+        (contract, context)
+    }
+
+    #[test]
+    fn settle_voter_application_rewards_coherent_jurors() {
+        let (mut contract, mut context) = juror_votes_for_test();
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let initial_total_supply = contract.get_total_supply().0;
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), Some(true));
+        assert!(contract.get_voter_details(bob_id).kyc_done);
+        assert!(contract.is_validated_voter(bob()));
+        assert!(!contract.is_validated_voter(user2()));
+        assert!(!contract.is_validated_voter(user3()));
+        // Slashed stakes of juror4, juror5 and user2 (20 + 20 + 51) and the juror incentive
+        // of 5 are shared by the three coherent jurors
+        assert_eq!(
+            contract.get_balance("juror1".to_owned()).0,
+            90 + 60 + 30 + 1
+        );
+        assert_eq!(
+            contract.get_balance("juror2".to_owned()).0,
+            110 + 40 + 30 + 1
+        );
+        assert_eq!(
+            contract.get_balance("juror3".to_owned()).0,
+            120 + 30 + 30 + 1
+        );
+        assert_eq!(contract.get_balance("juror4".to_owned()).0, 130);
+        assert_eq!(contract.get_balance("juror5".to_owned()).0, 130);
+        assert_eq!(contract.get_balance(user2()).0, 99);
+        for juror in ["juror1", "juror2", "juror3", "juror4", "juror5"].iter() {
+            assert_eq!(contract.get_locked_balance(juror.to_string()).0, 0);
+        }
+        assert_eq!(contract.get_locked_balance(user2()).0, 0);
+        // bob gets back the stake of 50 with the quadratic reward of 7
+        let bob_balance = 1_000_000_000_000_000u128 / 3 - 50 + 50 + 7;
+        assert_eq!(contract.get_balance(bob()).0, bob_balance);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        // The voter reward of 7 and the shares of the juror incentive are minted, and the
+        // remainder 1 of the slashed stakes is burned. The jurors get no more than the juror
+        // incentive for the settlement.
+        let juror_incentive_minted = contract.get_total_supply().0 + 1 - 7 - initial_total_supply;
+        assert_eq!(juror_incentive_minted, 3);
+        assert!(juror_incentive_minted <= JUROR_INCENTIVE);
+    }
+
+    #[test]
+    fn settle_voter_application_updates_juror_reputation() {
+        let (mut contract, mut context) = juror_votes_for_test();
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert_eq!(
+            contract.get_juror_reputation(juror1_id),
+            JurorReputation::default()
+        );
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        let reputation =
+            |juror: &str| contract.get_juror_reputation(contract.get_user_id(&juror.to_owned()));
+        assert_eq!(
+            reputation("juror1"),
+            JurorReputation {
+                rounds_drawn: 1,
+                rounds_revealed: 1,
+                coherent_votes: 1,
+                slashes: 0,
+            }
+        );
+        assert_eq!(
+            reputation("juror4"),
+            JurorReputation {
+                rounds_drawn: 1,
+                rounds_revealed: 1,
+                coherent_votes: 0,
+                slashes: 1,
+            }
+        );
+        // juror5 didn't reveal the vote
+        assert_eq!(
+            reputation("juror5"),
+            JurorReputation {
+                rounds_drawn: 1,
+                rounds_revealed: 0,
+                coherent_votes: 0,
+                slashes: 1,
+            }
+        );
+        assert_eq!(reputation("juror1").draw_weight_percent(), 150);
+        assert_eq!(reputation("juror4").draw_weight_percent(), 50);
+        assert_eq!(JurorReputation::default().draw_weight_percent(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is already settled")]
+    fn settle_voter_application_twice_fails() {
+        let (mut contract, mut context) = juror_votes_for_test();
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        contract.settle_voter_application(U128(bob_id));
+    }
+
+    #[test]
+    fn voter_application_phases() {
+        let (contract, mut context) = voter_stake();
+        let bob_id = contract.get_user_id(&bob());
+        let user2_id = contract.get_user_id(&user2());
+        assert_eq!(
+            contract.get_voter_application_phase(U128(user2_id)),
+            ApplicationPhase::NotStarted
+        );
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::JurorApplication
+        );
+        let phases = [
+            (draw_phase_time(), ApplicationPhase::Draw),
+            (commit_phase_time(), ApplicationPhase::Commit),
+            (reveal_phase_time(), ApplicationPhase::Reveal),
+            (appeal_phase_time(), ApplicationPhase::Appeal),
+            (settlement_phase_time(), ApplicationPhase::Settlement),
+        ];
+        for (time, phase) in phases.iter() {
+            context.block_timestamp = *time;
+            testing_env!(context.clone());
+            assert_eq!(contract.get_voter_application_phase(U128(bob_id)), *phase);
+        }
+        let timeline = contract.get_voter_application_timeline(U128(bob_id));
+        assert_eq!(timeline.reveal_end, appeal_phase_time());
+        assert_eq!(timeline.appeal_end, settlement_phase_time());
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in Draw phase, not in JurorApplication phase")]
+    fn apply_jurors_after_application_period_fails() {
+        let (contract, mut context) = voter_stake();
+        context.block_timestamp = draw_phase_time();
+        let (contract, context) = create_a_user(
+            "juror1".to_owned(),
+            "juror1######XXXXX".to_owned(),
+            contract,
+            context,
+        );
+        apply_jurors_for_test_function(bob(), "juror1".to_owned(), 60, contract, context);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in JurorApplication phase, not in Draw phase")]
+    fn draw_jurors_during_application_period_fails() {
+        let (mut contract, _context) = voter_stake();
+        contract.draw_jurors(bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "Jurors are already drawn")]
+    fn draw_jurors_twice_fails() {
+        let (mut contract, _context) = draw_jurors_for_test();
+        contract.draw_jurors(bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in Reveal phase, not in Settlement phase")]
+    fn settle_voter_application_before_reveal_end_fails() {
+        let (mut contract, _context) = juror_votes_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Not enough jurors have applied, 6 applied but at least 10 are required"
+    )]
+    fn draw_jurors_with_too_few_applicants_fails() {
+        let (mut contract, mut context) = juror_applications_for_test(ValidationConfig::default());
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+    }
+
+    #[test]
+    fn settle_voter_application_without_jury_returns_stakes() {
+        let (mut contract, mut context) = juror_applications_for_test(ValidationConfig::default());
+        // Too few jurors applied, so no jury is drawn before the settlement phase
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let total_supply = contract.get_total_supply().0;
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), None);
+        assert_eq!(contract.get_total_supply().0, total_supply);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        assert_eq!(contract.get_locked_balance(user2()).0, 0);
+        assert_eq!(contract.get_balance(user2()).0, 150);
+        for juror in ["juror1", "juror2", "juror3", "juror4", "juror5"].iter() {
+            assert_eq!(contract.get_locked_balance(juror.to_string()).0, 0);
+            assert_eq!(contract.get_balance(juror.to_string()).0, 150);
+        }
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::NotStarted
+        );
+        // The application is cleared, so bob can update the profile and stake again
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+        contract.create_voter_stake(40);
+        assert_eq!(contract.get_locked_balance(bob()).0, 40);
+    }
+
+    #[test]
+    fn update_voter_profile_clears_application_and_credits_storage() {
+        let (mut contract, mut context) = juror_votes_for_test();
+        context.block_timestamp = settlement_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        let accounts: Vec<AccountId> = vec![
+            bob(),
+            user2(),
+            "juror1".to_owned(),
+            "juror2".to_owned(),
+            "juror3".to_owned(),
+            "juror4".to_owned(),
+            "juror5".to_owned(),
+        ];
+        let available = |contract: &FungibleToken, account_id: &AccountId| {
+            contract
+                .storage_balance_of(account_id.clone())
+                .unwrap()
+                .available
+                .0 as i128
+        };
+        let initial_available: Vec<i128> = accounts
+            .iter()
+            .map(|account_id| available(&contract, account_id))
+            .collect();
+        context.signer_account_id = bob();
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+        assert!(contract.get_draw_transcript(U128(bob_id), 0).is_none());
+        assert!(contract.get_voter_appeals(U128(bob_id)).is_empty());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert!(contract
+            .get_juror_application(U128(bob_id), juror1_id)
+            .is_none());
+        assert!(contract
+            .get_applications_of_juror(juror1_id, 0, 10)
+            .is_empty());
+        // Each juror is credited its own storage, and all the released storage is credited
+        let mut credited = 0;
+        for (account_id, initial_available) in accounts.iter().zip(initial_available) {
+            let account_credit = available(&contract, account_id) - initial_available;
+            if *account_id != bob() {
+                assert!(account_credit > 0);
+            }
+            credited += account_credit;
+        }
+        let released_storage = context.storage_usage as i128 - env::storage_usage() as i128;
+        assert_eq!(credited, released_storage * STORAGE_PRICE_PER_BYTE as i128);
+    }
+
+    #[test]
+    fn draw_jurors_draws_jury_size_percent_of_applicants() {
+        let (mut contract, mut context) = juror_applications_for_test(ValidationConfig {
+            jury_size_percent: 50,
+            min_jurors: 2,
+            voter_reward_curve: VoterRewardCurve::Quadratic,
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        });
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_selected_jurors(bob_id).len(), 3);
+    }
+
+    #[test]
+    fn reputation_weighted_draw_draws_jury_size_jurors() {
+        let (mut contract, mut context) = juror_applications_for_test(ValidationConfig {
+            jury_size_percent: 50,
+            min_jurors: 2,
+            voter_reward_curve: VoterRewardCurve::Quadratic,
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: true,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        });
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_selected_jurors(bob_id).len(), 3);
+    }
+
+    #[test]
+    fn reputation_weighted_draw_uses_reputation_when_applying() {
+        let votes = [
+            ("juror1", "1juror1secret"),
+            ("juror2", "1juror2secret"),
+            ("juror3", "1juror3secret"),
+            ("juror4", "0juror4secret"),
+            ("juror5", "0juror5secret"),
+        ];
+        let validation_config = ValidationConfig {
+            reputation_weighted_draw: true,
+            ..test_validation_config()
+        };
+        let (contract, mut context) =
+            cast_juror_votes_with_config_for_test(validation_config, &votes, 4);
+        // user3 stakes while the application of bob isn't settled yet
+        context.block_timestamp = appeal_phase_time();
+        let (mut contract, mut context) =
+            create_a_user(user3(), "user3profile".to_owned(), contract, context);
+        contract.create_voter_stake(50);
+        let user3_id = contract.get_user_id(&user3());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        let juror4_id = contract.get_user_id(&"juror4".to_owned());
+        // juror1 applies without a reputation, so it's weighted 100%
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.apply_jurors(user3(), 60);
+        assert_eq!(
+            contract.get_juror_draw_weight(U128(user3_id), juror1_id),
+            60
+        );
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        contract.settle_voter_application(U128(contract.get_user_id(&bob())));
+        assert_eq!(
+            contract
+                .get_juror_reputation(juror1_id)
+                .draw_weight_percent(),
+            150
+        );
+        // The weight of juror1 keeps the reputation it applied with, while juror4 applies
+        // after losing its vote and is weighted 50%
+        assert_eq!(
+            contract.get_juror_draw_weight(U128(user3_id), juror1_id),
+            60
+        );
+        context.signer_account_id = "juror4".to_owned();
+        testing_env!(context.clone());
+        contract.apply_jurors(user3(), 20);
+        assert_eq!(
+            contract.get_juror_draw_weight(U128(user3_id), juror4_id),
+            10
+        );
+        assert_eq!(contract.get_juror_stakes(U128(user3_id), juror4_id), 20);
+    }
+
+    #[test]
+    fn draw_transcript_is_verified_off_chain() {
+        let (contract, _context) = draw_jurors_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let transcript = contract.get_draw_transcript(U128(bob_id), 0).unwrap();
+        let transcript_json = serde_json::to_string(&transcript).unwrap();
+        let transcript: draw_verifier::DrawTranscript =
+            serde_json::from_str(&transcript_json).unwrap();
+        assert_eq!(draw_verifier::verify(&transcript), Ok(()));
+        // All six applicants are drawn, with stakes 51 + 60 + 40 + 30 + 20 + 20
+        assert_eq!(transcript.candidates.len(), 6);
+        assert_eq!(transcript.weights.iter().sum::<u128>(), 221);
+        let selected_jurors = contract.get_selected_jurors(bob_id);
+        assert_eq!(transcript.draws.len() as u64, selected_jurors.len());
+        for draw in transcript.draws.iter() {
+            assert!(selected_jurors.contains(&draw.juror_user_id));
+        }
+        let drawn_weight: u128 = transcript.draws.iter().map(|draw| draw.weight).sum();
+        assert_eq!(drawn_weight, 221);
+        assert!(contract.get_draw_transcript(U128(bob_id), 1).is_none());
+    }
+
+    #[test]
+    fn draw_transcript_of_part_of_the_applicants_is_verified_off_chain() {
+        let (contract, _context) = draw_jurors_with_config_for_test(ValidationConfig {
+            jury_size_percent: 10,
+            min_jurors: 4,
+            ..test_validation_config()
+        });
+        let bob_id = contract.get_user_id(&bob());
+        let transcript = contract.get_draw_transcript(U128(bob_id), 0).unwrap();
+        let transcript_json = serde_json::to_string(&transcript).unwrap();
+        let mut transcript: draw_verifier::DrawTranscript =
+            serde_json::from_str(&transcript_json).unwrap();
+        assert_eq!(draw_verifier::verify(&transcript), Ok(()));
+        assert_eq!(transcript.candidates.len(), 6);
+        assert_eq!(transcript.draws.len(), 4);
+
+        // Recording an applicant who wasn't drawn, with its own weight, fails the check
+        let drawn: Vec<u128> = transcript
+            .draws
+            .iter()
+            .map(|draw| draw.juror_user_id)
+            .collect();
+        let undrawn = transcript
+            .candidates
+            .iter()
+            .position(|candidate| !drawn.contains(candidate))
+            .unwrap();
+        transcript.draws[0].juror_user_id = transcript.candidates[undrawn];
+        transcript.draws[0].weight = transcript.weights[undrawn];
+        assert!(draw_verifier::verify(&transcript).is_err());
+    }
+
+    #[test]
+    fn draw_jurors_draws_at_least_min_jurors() {
+        let (mut contract, mut context) = juror_applications_for_test(ValidationConfig {
+            jury_size_percent: 10,
+            min_jurors: 4,
+            voter_reward_curve: VoterRewardCurve::Quadratic,
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        });
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_selected_jurors(bob_id).len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Jury size percent should be between 1 and 100")]
+    fn test_initialize_with_invalid_jury_size_percent_fails() {
+        let context = get_context(carol());
+        testing_env!(context);
+        let total_supply = 1_000_000_000_000_000u128;
+        FungibleToken::new_with_config(
+            bob(),
+            total_supply.into(),
+            FungibleTokenMetadata::default(),
+            ValidationConfig {
+                jury_size_percent: 150,
+                min_jurors: 10,
+                voter_reward_curve: VoterRewardCurve::Quadratic,
+                appeal_fee: 10.into(),
+                reputation_weighted_draw: false,
+                reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+            },
+        );
+    }
+
+    #[test]
+    fn voter_reward_curves() {
+        assert_eq!(VoterRewardCurve::Quadratic.reward(0), 0);
+        assert_eq!(VoterRewardCurve::Quadratic.reward(50), 7);
+        assert_eq!(VoterRewardCurve::Quadratic.reward(49), 7);
+        assert_eq!(VoterRewardCurve::Quadratic.reward(48), 6);
+        assert_eq!(
+            VoterRewardCurve::Quadratic.reward(u128::MAX),
+            u64::MAX as u128
+        );
+        let step = VoterRewardCurve::Step {
+            threshold: 2.into(),
+            fixed_reward: 1.into(),
+        };
+        assert_eq!(step.reward(50), 1);
+        assert_eq!(step.reward(2), 1);
+        assert_eq!(step.reward(1), 0);
+    }
+
+    #[test]
+    fn get_voter_reward_uses_configured_curve() {
+        let (contract, _context) = voter_stake_with_config(ValidationConfig {
+            jury_size_percent: 100,
+            min_jurors: 3,
+            voter_reward_curve: VoterRewardCurve::Step {
+                threshold: 20.into(),
+                fixed_reward: 10.into(),
+            },
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        });
+        assert_eq!(contract.get_voter_reward(50.into()).0, 10);
+        assert_eq!(contract.get_voter_reward(16.into()).0, 8);
+    }
+
+    #[test]
+    fn settle_voter_application_records_rejection() {
+        let votes = [
+            ("juror1", "0juror1secret"),
+            ("juror2", "0juror2secret"),
+            ("juror3", "1juror3secret"),
+        ];
+        let (mut contract, mut context) = cast_juror_votes_for_test(&votes, 3);
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let bob_balance = contract.get_balance(bob()).0;
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), Some(false));
+        let voter = contract.get_voter_details(bob_id);
+        assert!(!voter.kyc_done);
+        assert!(voter.kyc_rejected);
+        assert!(!contract.is_validated_voter(bob()));
+        // The stake of a rejected voter is not returned
+        assert_eq!(contract.get_balance(bob()).0, bob_balance);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+    }
+
+    #[test]
+    fn settle_voter_application_without_revealed_votes_returns_voter_stake() {
+        let votes = [("juror1", "0juror1secret"), ("juror2", "0juror2secret")];
+        let (mut contract, mut context) = cast_juror_votes_for_test(&votes, 0);
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let bob_balance = contract.get_balance(bob()).0;
+        let total_supply = contract.get_total_supply().0;
+        contract.settle_voter_application(U128(bob_id));
+        // No juror judged bob, so bob is neither accepted nor rejected and gets back the stake
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), None);
+        let voter = contract.get_voter_details(bob_id);
+        assert!(!voter.kyc_done);
+        assert!(!voter.kyc_rejected);
+        assert_eq!(contract.get_balance(bob()).0, bob_balance + 50);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        // The jurors who didn't reveal their vote are slashed, 51 + 60 + 40 + 30 + 20 + 20
+        assert_eq!(contract.get_balance("juror1".to_owned()).0, 90);
+        assert_eq!(contract.get_locked_balance("juror1".to_owned()).0, 0);
+        assert_eq!(contract.get_total_supply().0, total_supply - 221);
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert_eq!(contract.get_juror_reputation(juror1_id).slashes, 1);
+        // The application is cleared and bob can stake again
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::NotStarted
+        );
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.create_voter_stake(40);
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::JurorApplication
+        );
+    }
+
+    #[test]
+    fn settle_voter_application_with_tied_votes_returns_revealed_stakes() {
+        let votes = [("juror1", "1juror1secret"), ("juror2", "0juror2secret")];
+        let (mut contract, mut context) = cast_juror_votes_for_test(&votes, 2);
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let bob_balance = contract.get_balance(bob()).0;
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), None);
+        assert!(!contract.get_voter_details(bob_id).kyc_rejected);
+        assert_eq!(contract.get_balance(bob()).0, bob_balance + 50);
+        // Both jurors who revealed get back their stake and share the stakes of the four
+        // jurors who didn't reveal (51 + 30 + 20 + 20), without the juror incentive
+        assert_eq!(contract.get_balance("juror1".to_owned()).0, 150 + 60);
+        assert_eq!(contract.get_balance("juror2".to_owned()).0, 150 + 60);
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        let reputation = contract.get_juror_reputation(juror1_id);
+        assert_eq!(reputation.rounds_revealed, 1);
+        assert_eq!(reputation.coherent_votes, 0);
+        assert_eq!(reputation.slashes, 0);
+    }
+
+    #[test]
+    fn revealer_of_tied_votes_outranks_non_revealer() {
+        let votes = [("juror1", "1juror1secret"), ("juror2", "0juror2secret")];
+        let (mut contract, mut context) = cast_juror_votes_for_test(&votes, 2);
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        let non_revealers: Vec<u128> = contract
+            .get_selected_juror_list(U128(bob_id), 0, 0, 10)
+            .iter()
+            .filter(|juror| juror.application.vote.is_none())
+            .map(|juror| juror.juror_user_id.0)
+            .collect();
+        assert_eq!(non_revealers.len(), 4);
+        contract.settle_voter_application(U128(bob_id));
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        let revealer_weight = contract
+            .get_juror_reputation(juror1_id)
+            .draw_weight_percent();
+        assert_eq!(revealer_weight, 100);
+        for juror_user_id in non_revealers {
+            let reputation = contract.get_juror_reputation(juror_user_id);
+            assert_eq!(reputation.draw_weight_percent(), 50);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough balance to stake 200, the balance is 150")]
+    fn apply_jurors_with_stake_above_balance_fails() {
+        let (contract, context) = voter_stake();
+        let (contract, context) = create_a_user(
+            "juror1".to_owned(),
+            "juror1######XXXXX".to_owned(),
+            contract,
+            context,
+        );
+        apply_jurors_for_test_function(bob(), "juror1".to_owned(), 200, contract, context);
+    }
+
+    #[test]
+    #[should_panic(expected = "You can't be a juror for your own voter application")]
+    fn apply_jurors_for_own_voter_application_fails() {
+        let (contract, context) = voter_stake();
+        apply_jurors_for_test_function(bob(), bob(), 10, contract, context);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "The voter was drawn as your juror within the reciprocal jury cooldown"
+    )]
+    fn apply_jurors_for_own_juror_within_cooldown_fails() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let user2_id = contract.get_user_id(&user2());
+        assert!(contract.get_selected_jurors(bob_id).contains(&user2_id));
+        assert_eq!(
+            contract.get_last_jury_service(user2_id, bob_id),
+            Some(draw_phase_time())
+        );
+        context.signer_account_id = user2();
+        testing_env!(context.clone());
+        contract.create_voter_stake(20);
+        apply_jurors_for_test_function(user2(), bob(), 10, contract, context);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "You have declared a relationship with the voter, or the voter with you"
+    )]
+    fn apply_jurors_with_declared_relationship_fails() {
+        let (contract, context) = voter_stake();
+        let (mut contract, context) = create_a_user(
+            "juror1".to_owned(),
+            "juror1######XXXXX".to_owned(),
+            contract,
+            context,
+        );
+        contract.declare_relationship(bob());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert_eq!(
+            contract.get_declared_relationships(juror1_id),
+            vec![contract.get_user_id(&bob())]
+        );
+        apply_jurors_for_test_function(bob(), "juror1".to_owned(), 10, contract, context);
+    }
+
+    #[test]
+    fn draw_jurors_withdraws_drawn_applicant_with_declared_relationship() {
+        let (mut contract, mut context) = juror_applications_for_test(test_validation_config());
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.declare_relationship(bob());
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        let selected_jurors = contract.get_selected_jurors(bob_id);
+        assert_eq!(selected_jurors.len(), 5);
+        assert!(!selected_jurors.contains(&juror1_id));
+        assert!(contract.is_juror_stake_withdrawn(U128(bob_id), juror1_id));
+        assert_eq!(contract.get_locked_balance("juror1".to_owned()).0, 0);
+        assert_eq!(contract.get_balance("juror1".to_owned()).0, 150);
+        // juror1 is withdrawn when drawn, the other applicants are drawn without the check
+        let transcript = contract.get_draw_transcript(U128(bob_id), 0).unwrap();
+        assert_eq!(transcript.draws.len(), 6);
+        for draw in transcript.draws.iter() {
+            assert_eq!(draw.withdrawn, draw.juror_user_id.0 == juror1_id);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in progress")]
+    fn declare_relationship_during_own_voter_application_fails() {
+        let (mut contract, mut context) = voter_stake();
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.declare_relationship(user2());
+    }
+
+    // bob is validated and vouches with a bond of 100 for user3, whose three jurors all
+    // cast `vote`. Returns the balance of bob before vouching.
+    fn settle_vouched_voter_application_for_test(
+        vote: &str,
+    ) -> (FungibleToken, VMContext, Balance) {
+        let (mut contract, mut context) = juror_votes_for_test();
+        let start = settlement_phase_time();
+        context.block_timestamp = start;
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        let (mut contract, mut context) =
+            create_a_user(user3(), "user3profile".to_owned(), contract, context);
+        contract.create_voter_stake(50);
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        let bob_balance = contract.get_balance(bob()).0;
+        contract.vouch_for_voter(user3(), 100);
+        assert_eq!(contract.get_locked_balance(bob()).0, 100);
+        assert_eq!(contract.ft_balance_of(bob()).0, bob_balance);
+        let user3_id = contract.get_user_id(&user3());
+        let vouches = contract.get_voter_vouches(user3_id);
+        assert_eq!(vouches.len(), 1);
+        assert_eq!(vouches[0].voucher, bob_id);
+        assert_eq!(vouches[0].bond.0, 100);
+
+        let jurors = ["juror1", "juror2", "juror3"];
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.apply_jurors(user3(), 10);
+        }
+        context.random_seed = rand_vector();
+        context.block_timestamp = start + JUROR_APPLICATION_PERIOD;
+        testing_env!(context.clone());
+        contract.draw_jurors(user3());
+        context.block_timestamp += DRAW_PERIOD;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.commit_juror_vote(user3(), vote_commit(juror, vote));
+        }
+        context.block_timestamp += COMMIT_PERIOD;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.reveal_juror_vote(user3(), vote.to_owned());
+        }
+        context.block_timestamp += REVEAL_PERIOD + APPEAL_PERIOD;
+        testing_env!(context.clone());
+        contract.settle_voter_application(U128(user3_id));
+        (contract, context, bob_balance)
+    }
+
+    #[test]
+    fn vouch_bond_is_returned_when_voter_is_accepted() {
+        let (contract, _context, bob_balance) =
+            settle_vouched_voter_application_for_test("1user3secret");
+        assert!(contract.is_validated_voter(user3()));
+        assert_eq!(contract.get_balance(bob()).0, bob_balance);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+    }
+
+    #[test]
+    fn vouch_bond_is_slashed_when_voter_is_rejected() {
+        let (contract, _context, bob_balance) =
+            settle_vouched_voter_application_for_test("0user3secret");
+        assert!(!contract.is_validated_voter(user3()));
+        assert_eq!(contract.get_balance(bob()).0, bob_balance - 100);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        assert_eq!(contract.ft_balance_of(bob()).0, bob_balance - 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only validated voters can vouch")]
+    fn vouch_by_unvalidated_voter_fails() {
+        let (mut contract, _context) = voter_stake();
+        contract.vouch_for_voter(bob(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "You have vouched for the voter")]
+    fn apply_jurors_after_vouching_fails() {
+        let (contract, context, _bob_balance) =
+            settle_vouched_voter_application_for_test("1user3secret");
+        let (mut contract, mut context) = create_a_user(
+            "voter4".to_owned(),
+            "voter4profile".to_owned(),
+            contract,
+            context,
+        );
+        contract.create_voter_stake(50);
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.vouch_for_voter("voter4".to_owned(), 10);
+        contract.apply_jurors("voter4".to_owned(), 10);
+    }
+
+    // bob is validated and claims expertise in the health department, seeded with juror1,
+    // juror2 and juror3 as its experts. Returns the application id.
+    fn expertise_application_for_test() -> (FungibleToken, VMContext, U128) {
+        let (mut contract, mut context) = juror_votes_for_test();
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        contract.add_department("health".to_owned());
+        for juror in ["juror1", "juror2", "juror3"].iter() {
+            contract.add_department_expert("health".to_owned(), juror.to_string());
+        }
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        let application_id =
+            contract.claim_expertise("health".to_owned(), "bobhealthprofile".to_owned(), 20);
+        assert_eq!(
+            contract.get_expertise_application_id(bob_id, "health".to_owned()),
+            Some(application_id)
+        );
+        assert_eq!(
+            contract.get_voter_application_phase(application_id),
+            ApplicationPhase::JurorApplication
+        );
+        (contract, context, application_id)
+    }
+
+    // The three experts of the health department judge the expertise application of bob
+    // with `vote` and the application is settled.
+    fn settle_expertise_application_for_test(vote: &str) -> (FungibleToken, VMContext, U128) {
+        let (mut contract, mut context, application_id) = expertise_application_for_test();
+        let start = context.block_timestamp;
+        let jurors = ["juror1", "juror2", "juror3"];
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.apply_expertise_jurors(application_id, 10);
+        }
+        context.random_seed = rand_vector();
+        context.block_timestamp = start + JUROR_APPLICATION_PERIOD;
+        testing_env!(context.clone());
+        contract.draw_expertise_jurors(application_id);
+        context.block_timestamp += DRAW_PERIOD;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.commit_expertise_juror_vote(application_id, vote_commit(juror, vote));
+        }
+        context.block_timestamp += COMMIT_PERIOD;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.to_string();
+            testing_env!(context.clone());
+            contract.reveal_expertise_juror_vote(application_id, vote.to_owned());
+        }
+        context.block_timestamp += REVEAL_PERIOD + APPEAL_PERIOD;
+        testing_env!(context.clone());
+        contract.settle_voter_application(application_id);
+        (contract, context, application_id)
+    }
+
+    #[test]
+    fn accepted_expertise_application_adds_department_expert() {
+        let (contract, _context, application_id) =
+            settle_expertise_application_for_test("1healthsecret");
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_voter_ruling(application_id), Some(true));
+        assert!(contract.is_department_expert("health".to_owned(), bob_id));
+        assert_eq!(
+            contract.get_department_experts("health".to_owned()).len(),
+            4
+        );
+        assert!(contract.is_validated_voter(bob()));
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        let expertise_application = contract.get_expertise_application(application_id).unwrap();
+        assert_eq!(expertise_application.user_id, bob_id);
+        assert_eq!(expertise_application.department, "health");
+    }
+
+    #[test]
+    fn expertise_application_id_is_not_a_user_id() {
+        let (contract, context, application_id) = expertise_application_for_test();
+        assert_eq!(application_id.0, EXPERTISE_APPLICATION_ID_START + 1);
+        // The id is above the integers JSON numbers hold exactly, so it is a string
+        assert_eq!(
+            serde_json::to_string(&application_id).unwrap(),
+            "\"18446744073709551617\""
+        );
+        // bob, user2 and the five jurors are users 1 to 7
+        let (contract, _context) =
+            create_a_user(user3(), "user3profile".to_owned(), contract, context);
+        let user3_id = contract.get_user_id(&user3());
+        assert_eq!(user3_id, 8);
+        assert_eq!(
+            contract.get_voter_details(user3_id).profile_hash,
+            "user3profile"
+        );
+    }
+
+    #[test]
+    fn update_voter_profile_revokes_expertise() {
+        let (mut contract, mut context, application_id) =
+            settle_expertise_application_for_test("1healthsecret");
+        let bob_id = contract.get_user_id(&bob());
+        assert!(contract.is_department_expert("health".to_owned(), bob_id));
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+        assert!(!contract.is_validated_voter(bob()));
+        assert!(!contract.is_department_expert("health".to_owned(), bob_id));
+        assert_eq!(
+            contract.get_department_experts("health".to_owned()).len(),
+            3
+        );
+        assert_eq!(
+            contract.get_expertise_application_id(bob_id, "health".to_owned()),
+            None
+        );
+        assert!(contract.get_expertise_application(application_id).is_none());
+        assert_eq!(
+            contract.get_voter_application_phase(application_id),
+            ApplicationPhase::NotStarted
+        );
+    }
+
+    #[test]
+    fn rejected_expertise_application_can_be_claimed_again() {
+        let (mut contract, mut context, application_id) =
+            settle_expertise_application_for_test("0healthsecret");
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_voter_ruling(application_id), Some(false));
+        assert!(!contract.is_department_expert("health".to_owned(), bob_id));
+        // Rejecting the expertise doesn't reject the voter
+        assert!(contract.is_validated_voter(bob()));
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        let new_application_id =
+            contract.claim_expertise("health".to_owned(), "bobnewproof".to_owned(), 20);
+        assert_ne!(new_application_id, application_id);
+        assert!(contract.get_expertise_application(application_id).is_none());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Only the experts of the health department can be jurors for the application"
+    )]
+    fn apply_expertise_jurors_by_non_expert_fails() {
+        let (mut contract, mut context, application_id) = expertise_application_for_test();
+        context.signer_account_id = "juror4".to_owned();
+        testing_env!(context.clone());
+        contract.apply_expertise_jurors(application_id, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only validated voters can claim expertise")]
+    fn claim_expertise_by_unvalidated_voter_fails() {
+        let (mut contract, _context) = voter_stake();
+        contract.add_department("health".to_owned());
+        contract.claim_expertise("health".to_owned(), "user2healthprofile".to_owned(), 20);
+    }
+
+    #[test]
+    fn clearing_voter_application_releases_undrawn_juror_stakes() {
+        let (mut contract, mut context) =
+            juror_applications_for_test(appeal_test_validation_config());
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        let drawn_juror_id = contract.get_selected_jurors(bob_id).to_vec()[0];
+        let (mut contract, mut context) =
+            cast_round_votes_for_test("1secret", commit_phase_time(), contract, context);
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        contract.settle_voter_application(U128(bob_id));
+        let total_supply = contract.get_total_supply().0;
+
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+        for juror_user_id in 2..8 {
+            let juror = contract.get_user_account_id(juror_user_id);
+            assert_eq!(contract.get_locked_balance(juror.clone()).0, 0);
+            if juror_user_id != drawn_juror_id {
+                assert_eq!(contract.get_balance(juror).0, 150);
+            }
+        }
+        assert_eq!(contract.get_total_supply().0, total_supply);
+    }
+
+    #[test]
+    fn update_voter_profile_keeps_history() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        storage_deposit_for_test(bob(), &mut contract, &context);
+        contract.create_voter_profile("c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned());
+        context.block_timestamp = 100;
+        testing_env!(context.clone());
+        contract.update_voter_profile("d2e2b90685d7f855e093f1f3c02265fa16d24".to_owned());
+        let bob_id = contract.get_user_id(&bob());
+        let voter = contract.get_voter_details(bob_id);
+        assert_eq!(
+            "d2e2b90685d7f855e093f1f3c02265fa16d24".to_owned(),
+            voter.profile_hash
+        );
+        let history = contract.get_voter_profile_history(bob_id);
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0].profile_hash,
+            "c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned()
+        );
+        assert_eq!(history[0].timestamp, 0);
+        assert_eq!(
+            history[1].profile_hash,
+            "d2e2b90685d7f855e093f1f3c02265fa16d24".to_owned()
+        );
+        assert_eq!(history[1].timestamp, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in progress")]
+    fn update_voter_profile_during_validation_fails() {
+        let (mut contract, mut context) = voter_stake();
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+    }
+
+    #[test]
+    fn update_validated_voter_profile_opens_fresh_round() {
+        let (mut contract, mut context) = juror_votes_for_test();
+        context.block_timestamp = settlement_phase_time();
+        testing_env!(context.clone());
+        let bob_id = contract.get_user_id(&bob());
+        contract.settle_voter_application(U128(bob_id));
+        assert!(contract.is_validated_voter(bob()));
+
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.update_voter_profile("bobnewprofile".to_owned());
+        assert!(!contract.is_validated_voter(bob()));
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::NotStarted
+        );
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), None);
+        assert_eq!(contract.get_voter_profile_history(bob_id).len(), 2);
+
+        contract.create_voter_stake(40);
+        assert_eq!(contract.get_voter_stake(U128(bob_id)), 40);
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::JurorApplication
+        );
+        let (contract, _context) =
+            apply_jurors_for_test_function(bob(), "juror1".to_owned(), 10, contract, context);
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert_eq!(contract.get_juror_stakes(U128(bob_id), juror1_id), 10);
+    }
+
+    fn appeal_test_validation_config() -> ValidationConfig {
+        ValidationConfig {
+            jury_size_percent: 10,
+            min_jurors: 1,
+            voter_reward_curve: VoterRewardCurve::Quadratic,
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        }
+    }
+
+    // Commits and reveals the same vote for every juror of the current round, starting at
+    // the `draw_end` of the round.
+    fn cast_round_votes_for_test(
+        vote: &str,
+        draw_end: u64,
+        mut contract: FungibleToken,
+        mut context: VMContext,
+    ) -> (FungibleToken, VMContext) {
+        let bob_id = contract.get_user_id(&bob());
+        let jurors: Vec<AccountId> = contract
+            .get_selected_jurors(bob_id)
+            .iter()
+            .map(|juror_user_id| contract.get_user_account_id(juror_user_id))
+            .collect();
+        context.block_timestamp = draw_end;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.clone();
+            testing_env!(context.clone());
+            contract.commit_juror_vote(bob(), vote_commit(juror, vote));
+        }
+        context.block_timestamp = draw_end + COMMIT_PERIOD;
+        for juror in jurors.iter() {
+            context.signer_account_id = juror.clone();
+            testing_env!(context.clone());
+            contract.reveal_juror_vote(bob(), vote.to_owned());
+        }
+        (contract, context)
+    }
+
+    // A single juror is drawn in the first round and rejects bob.
+    fn first_round_rejection_for_test() -> (FungibleToken, VMContext) {
+        let (mut contract, mut context) =
+            juror_applications_for_test(appeal_test_validation_config());
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(contract.get_selected_jurors(bob_id).len(), 1);
+        cast_round_votes_for_test("0firstroundsecret", commit_phase_time(), contract, context)
+    }
+
+    #[test]
+    fn appeal_draws_larger_jury_whose_ruling_is_final() {
+        let (mut contract, mut context) = first_round_rejection_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let first_juror_id = contract.get_round_selected_jurors(bob_id, 0).to_vec()[0];
+        let first_juror = contract.get_user_account_id(first_juror_id);
+        let first_juror_balance = contract.get_balance(first_juror.clone()).0;
+        let first_juror_stake = contract.get_juror_stakes(U128(bob_id), first_juror_id);
+
+        context.block_timestamp = appeal_phase_time();
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        let bob_balance = contract.get_balance(bob()).0;
+        contract.appeal_voter_application(bob());
+        assert_eq!(contract.get_balance(bob()).0, bob_balance - 10);
+        assert_eq!(contract.get_appeal_round(U128(bob_id)), 1);
+        let appeals = contract.get_voter_appeals(U128(bob_id));
+        assert_eq!(appeals.len(), 1);
+        assert_eq!(appeals[0].appellant, bob_id);
+        assert_eq!(appeals[0].appealed_ruling, Some(false));
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::Draw
+        );
+
+        contract.draw_jurors(bob());
+        let second_round = contract.get_round_selected_jurors(bob_id, 1);
+        assert_eq!(second_round.len(), 3);
+        assert!(!second_round.contains(&first_juror_id));
+        let second_round_balances: Vec<(AccountId, u128, u128)> = second_round
+            .iter()
+            .map(|juror_user_id| {
+                let juror = contract.get_user_account_id(juror_user_id);
+                let stake = contract.get_juror_stakes(U128(bob_id), juror_user_id);
+                (juror.clone(), contract.get_balance(juror).0, stake)
+            })
+            .collect();
+
+        let draw_end = appeal_phase_time() + DRAW_PERIOD;
+        let (mut contract, mut context) =
+            cast_round_votes_for_test("1secondroundsecret", draw_end, contract, context);
+        context.block_timestamp = draw_end + COMMIT_PERIOD + REVEAL_PERIOD + APPEAL_PERIOD;
+        testing_env!(context.clone());
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(contract.get_voter_ruling(U128(bob_id)), Some(true));
+        assert!(contract.is_validated_voter(bob()));
+        // bob gets back the appeal fee along with the stake of 50 and the reward of 7
+        assert_eq!(contract.get_balance(bob()).0, bob_balance + 50 + 7);
+        // The first round juror is slashed, the second round jurors share the juror
+        // incentive of 5
+        assert_eq!(contract.get_balance(first_juror).0, first_juror_balance);
+        for (juror, balance, stake) in second_round_balances {
+            assert_eq!(
+                contract.get_balance(juror).0,
+                balance + stake + 5 / 3 + first_juror_stake / 3
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the voter or the jurors who staked for the voter can appeal")]
+    fn appeal_by_non_participant_fails() {
+        let (contract, mut context) = first_round_rejection_for_test();
+        context.block_timestamp = appeal_phase_time();
+        let (mut contract, _context) = create_a_user(
+            "juror6".to_owned(),
+            "juror6######XXXXX".to_owned(),
+            contract,
+            context,
+        );
+        contract.appeal_voter_application(bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "Voter application is in Reveal phase, not in Appeal phase")]
+    fn appeal_before_reveal_end_fails() {
+        let (mut contract, mut context) = first_round_rejection_for_test();
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.appeal_voter_application(bob());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Not enough jurors have applied for the appeal, 2 remaining but 7 are required"
+    )]
+    fn appeal_without_enough_remaining_jurors_fails() {
+        let (mut contract, mut context) = first_round_rejection_for_test();
+        context.block_timestamp = appeal_phase_time();
+        context.signer_account_id = bob();
+        testing_env!(context.clone());
+        contract.appeal_voter_application(bob());
+        contract.draw_jurors(bob());
+        context.block_timestamp = appeal_phase_time() + appeal_phase_time() - draw_phase_time();
+        testing_env!(context.clone());
+        contract.appeal_voter_application(bob());
+    }
+
+    // Draws a single juror for bob and returns an applicant who was not drawn.
+    fn undrawn_juror_for_test() -> (FungibleToken, VMContext, AccountId) {
+        let (mut contract, mut context) =
+            juror_applications_for_test(appeal_test_validation_config());
+        context.random_seed = rand_vector();
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.draw_jurors(bob());
+        let bob_id = contract.get_user_id(&bob());
+        let drawn_juror_id = contract.get_selected_jurors(bob_id).to_vec()[0];
+        let undrawn_juror_id = if drawn_juror_id == 2 { 3 } else { 2 };
+        let undrawn_juror = contract.get_user_account_id(undrawn_juror_id);
+        (contract, context, undrawn_juror)
+    }
+
+    #[test]
+    fn withdraw_juror_stake_returns_stake_of_undrawn_juror() {
+        let (mut contract, mut context, juror) = undrawn_juror_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let juror_id = contract.get_user_id(&juror);
+        let stake = contract.get_juror_stakes(U128(bob_id), juror_id);
+        let balance = contract.get_balance(juror.clone()).0;
+        context.signer_account_id = juror.clone();
+        testing_env!(context.clone());
+        contract.withdraw_juror_stake(bob());
+        assert_eq!(contract.get_balance(juror.clone()).0, balance + stake);
+        assert_eq!(contract.get_locked_balance(juror).0, 0);
+        assert!(contract.is_juror_stake_withdrawn(U128(bob_id), juror_id));
+    }
+
+    #[test]
+    #[should_panic(expected = "Juror stake is already withdrawn")]
+    fn withdraw_juror_stake_twice_fails() {
+        let (mut contract, mut context, juror) = undrawn_juror_for_test();
+        context.signer_account_id = juror;
+        testing_env!(context.clone());
+        contract.withdraw_juror_stake(bob());
+        contract.withdraw_juror_stake(bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "You are drawn as a juror for the voter")]
+    fn withdraw_juror_stake_by_drawn_juror_fails() {
+        let (mut contract, mut context, _juror) = undrawn_juror_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let drawn_juror_id = contract.get_selected_jurors(bob_id).to_vec()[0];
+        context.signer_account_id = contract.get_user_account_id(drawn_juror_id);
+        testing_env!(context.clone());
+        contract.withdraw_juror_stake(bob());
+    }
+
+    #[test]
+    #[should_panic(expected = "Jurors are not drawn for the voter")]
+    fn withdraw_juror_stake_before_draw_fails() {
+        let (mut contract, mut context) = voter_stake();
+        context.signer_account_id = user2();
+        testing_env!(context.clone());
+        contract.withdraw_juror_stake(bob());
+    }
+
+    #[test]
+    fn juror_application_status_follows_the_voter_application() {
+        let (mut contract, mut context, undrawn_juror) = undrawn_juror_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let drawn_juror_id = contract.get_selected_jurors(bob_id).to_vec()[0];
+        let undrawn_juror_id = contract.get_user_id(&undrawn_juror);
+        let status = |contract: &FungibleToken, juror_user_id: u128| {
+            contract
+                .get_juror_application(U128(bob_id), juror_user_id)
+                .unwrap()
+                .status
+        };
+        assert_eq!(
+            status(&contract, drawn_juror_id),
+            JurorApplicationStatus::Drawn
+        );
+        assert_eq!(
+            status(&contract, undrawn_juror_id),
+            JurorApplicationStatus::Applied
+        );
+        let drawn_application = contract
+            .get_juror_application(U128(bob_id), drawn_juror_id)
+            .unwrap();
+        assert_eq!(drawn_application.round, Some(0));
+        assert_eq!(drawn_application.vote_commit, None);
+
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = contract.get_user_account_id(drawn_juror_id);
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit(&context.signer_account_id, "1secret"));
+        assert_eq!(
+            status(&contract, drawn_juror_id),
+            JurorApplicationStatus::Voted
+        );
+        context.block_timestamp = reveal_phase_time();
+        testing_env!(context.clone());
+        contract.reveal_juror_vote(bob(), "1secret".to_owned());
+        assert_eq!(
+            status(&contract, drawn_juror_id),
+            JurorApplicationStatus::Revealed
+        );
+
+        context.block_timestamp = settlement_phase_time();
+        context.signer_account_id = undrawn_juror;
+        testing_env!(context.clone());
+        contract.withdraw_juror_stake(bob());
+        contract.settle_voter_application(U128(bob_id));
+        assert_eq!(
+            status(&contract, drawn_juror_id),
+            JurorApplicationStatus::Settled
+        );
+        assert_eq!(
+            status(&contract, undrawn_juror_id),
+            JurorApplicationStatus::Withdrawn
+        );
+        assert_eq!(
+            contract.get_juror_vote(U128(bob_id), drawn_juror_id),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn juror_application_list_is_paginated() {
+        let (contract, _context) = juror_applications_for_test(test_validation_config());
+        let bob_id = contract.get_user_id(&bob());
+        let first_page = contract.get_juror_application_list(U128(bob_id), 0, 4);
+        assert_eq!(first_page.len(), 4);
+        assert_eq!(first_page[0].juror_account_id, user2());
+        assert_eq!(first_page[0].voter_account_id, bob());
+        assert_eq!(first_page[0].application.stake.0, 51);
+        assert_eq!(first_page[1].juror_account_id, "juror1".to_owned());
+        let second_page = contract.get_juror_application_list(U128(bob_id), 4, 4);
+        assert_eq!(second_page.len(), 2);
+        assert_eq!(second_page[1].juror_account_id, "juror5".to_owned());
+        assert_eq!(second_page[1].application.stake.0, 20);
+        assert!(contract
+            .get_juror_application_list(U128(bob_id), 6, 4)
+            .is_empty());
+        let json = near_sdk::serde_json::to_string(&first_page[0]).unwrap();
+        assert!(json.contains("\"juror_account_id\":\"user2.near\""));
+        assert!(json.contains("\"stake\":\"51\""));
+        assert!(json.contains("\"status\":\"Applied\""));
+    }
+
+    #[test]
+    fn selected_juror_list_is_paginated() {
+        let (contract, _context) = draw_jurors_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let jurors = contract.get_selected_juror_list(U128(bob_id), 0, 0, 10);
+        assert_eq!(jurors.len(), 6);
+        for juror in jurors.iter() {
+            assert_eq!(juror.application.status, JurorApplicationStatus::Drawn);
+            assert_eq!(
+                contract.get_user_account_id(juror.juror_user_id.0),
+                juror.juror_account_id
+            );
+        }
+        assert_eq!(
+            contract
+                .get_selected_juror_list(U128(bob_id), 0, 2, 3)
+                .len(),
+            3
+        );
+        assert_eq!(
+            contract
+                .get_selected_juror_list(U128(bob_id), 0, 5, 3)
+                .len(),
+            1
+        );
+        assert!(contract
+            .get_selected_juror_list(U128(bob_id), 1, 0, 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn applications_of_juror_lists_every_voter() {
+        let (mut contract, mut context) = voter_stake();
+        context.signer_account_id = user3();
+        storage_deposit_for_test(user3(), &mut contract, &context);
+        contract.create_voter_profile("user3profile".to_owned());
+        contract.transfer(user3(), 150.into());
+        contract.create_voter_stake(50);
+        context.signer_account_id = user2();
+        testing_env!(context.clone());
+        contract.apply_jurors(user3(), 53);
+        let user2_id = contract.get_user_id(&user2());
+        let applications = contract.get_applications_of_juror(user2_id, 0, 10);
+        assert_eq!(applications.len(), 2);
+        assert_eq!(applications[0].voter_account_id, bob());
+        assert_eq!(applications[0].application.stake.0, 51);
+        assert_eq!(applications[1].voter_account_id, user3());
+        assert_eq!(applications[1].application.stake.0, 53);
+        assert_eq!(contract.get_applications_of_juror(user2_id, 1, 10).len(), 1);
+        assert!(contract
+            .get_applications_of_juror(user2_id, 2, 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn events_are_logged_as_nep297_json() {
+        let event = Event::JurorDraw {
+            voter_user_id: 1.into(),
+            round: 0,
+            juror_user_ids: vec![2.into(), 3.into()],
+        };
+        let log = event.to_log_string();
+        assert!(log.starts_with("EVENT_JSON:"));
+        let json: serde_json::Value = serde_json::from_str(&log["EVENT_JSON:".len()..]).unwrap();
+        assert_eq!(json["standard"], "shivarthu");
+        assert_eq!(json["version"], "1.0.0");
+        assert_eq!(json["event"], "juror_draw");
+        assert_eq!(json["data"]["voter_user_id"], "1");
+        assert_eq!(json["data"]["round"], 0);
+        assert_eq!(json["data"]["juror_user_ids"][1], "3");
+    }
+
+    #[test]
+    fn token_events_are_logged_as_nep141_json() {
+        let event = FtEvent::FtTransfer(vec![FtTransfer {
+            old_owner_id: alice(),
+            new_owner_id: bob(),
+            amount: 10.into(),
+            memo: None,
+        }]);
+        let log = event.to_log_string();
+        assert!(log.starts_with("EVENT_JSON:"));
+        let json: serde_json::Value = serde_json::from_str(&log["EVENT_JSON:".len()..]).unwrap();
+        assert_eq!(json["standard"], "nep141");
+        assert_eq!(json["version"], "1.0.0");
+        assert_eq!(json["event"], "ft_transfer");
+        assert_eq!(json["data"][0]["old_owner_id"], alice());
+        assert_eq!(json["data"][0]["new_owner_id"], bob());
+        assert_eq!(json["data"][0]["amount"], "10");
+        assert!(json["data"][0].get("memo").is_none());
+    }
+
+    /// Sets up the blockchain like `testing_env!`, with `promise_result` as the result of
+    /// the promise the callback is called on.
+    fn testing_env_with_promise_result(context: VMContext, promise_result: PromiseResult) {
+        let storage = match env::take_blockchain_interface() {
+            Some(mut bi) => bi.as_mut_mocked_blockchain().unwrap().take_storage(),
+            None => Default::default(),
+        };
+        env::set_blockchain_interface(Box::new(MockedBlockchain::new(
+            context,
+            Default::default(),
+            Default::default(),
+            vec![promise_result],
+            storage,
+            Default::default(),
+        )));
+    }
+
+    fn ft_transfer_to_bob_for_test(amount: Balance) -> (FungibleToken, VMContext) {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        context.storage_usage = env::storage_usage();
+        storage_deposit_for_test(bob(), &mut contract, &context);
+        context.attached_deposit = 1;
+        testing_env!(context.clone());
+        contract.ft_transfer(bob(), amount.into(), Some("memo".to_owned()));
+        context.storage_usage = env::storage_usage();
+        context.attached_deposit = 0;
+        (contract, context)
+    }
+
+    #[test]
+    fn ft_transfer_moves_balance() {
+        let (contract, _context) = ft_transfer_to_bob_for_test(100);
+        let total_supply = 1_000_000_000_000_000u128;
+        assert_eq!(contract.ft_total_supply().0, total_supply);
+        assert_eq!(contract.ft_balance_of(bob()).0, 100);
+        assert_eq!(contract.ft_balance_of(carol()).0, total_supply - 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn ft_transfer_without_attached_deposit_fails() {
+        let (mut contract, context) = ft_transfer_to_bob_for_test(100);
+        testing_env!(context);
+        contract.ft_transfer(alice(), 10.into(), None);
+    }
+
+    #[test]
+    fn ft_resolve_transfer_refunds_unused_tokens() {
+        let (mut contract, mut context) = ft_transfer_to_bob_for_test(100);
+        context.predecessor_account_id = alice();
+        testing_env_with_promise_result(
+            context.clone(),
+            PromiseResult::Successful(b"\"40\"".to_vec()),
+        );
+        let used = contract.ft_resolve_transfer(carol(), bob(), 100.into());
+        assert_eq!(used.0, 60);
+        assert_eq!(contract.ft_balance_of(bob()).0, 60);
+
+        testing_env_with_promise_result(context, PromiseResult::Failed);
+        let used = contract.ft_resolve_transfer(carol(), bob(), 60.into());
+        assert_eq!(used.0, 0);
+        assert_eq!(contract.ft_balance_of(bob()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Method ft_resolve_transfer is private")]
+    fn ft_resolve_transfer_by_other_account_fails() {
+        let (mut contract, context) = ft_transfer_to_bob_for_test(100);
+        testing_env_with_promise_result(context, PromiseResult::Failed);
+        contract.ft_resolve_transfer(carol(), bob(), 100.into());
+    }
+
+    #[test]
+    fn storage_deposit_registers_account() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        context.storage_usage = env::storage_usage();
+        testing_env!(context.clone());
+        let min_balance = contract.storage_balance_bounds().min.0;
+        assert!(min_balance > 0);
+        assert!(contract.storage_balance_of(bob()).is_none());
+        context.attached_deposit = min_balance * 3;
+        testing_env!(context.clone());
+        let storage_balance = contract.storage_deposit(Some(bob()), None);
+        assert_eq!(storage_balance.total.0, min_balance * 3);
+        assert_eq!(storage_balance.available.0, min_balance * 2);
+
+        context.predecessor_account_id = bob();
+        context.attached_deposit = 1;
+        testing_env!(context.clone());
+        let storage_balance = contract.storage_withdraw(None);
+        assert_eq!(storage_balance.total.0, min_balance);
+        assert_eq!(storage_balance.available.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+    fn storage_deposit_below_minimum_fails() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        context.attached_deposit = contract.storage_balance_bounds().min.0 - 1;
+        testing_env!(context.clone());
+        contract.storage_deposit(Some(bob()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account bob.near is not registered")]
+    fn create_voter_profile_without_storage_registration_fails() {
+        let context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        contract.create_voter_profile("c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned());
+    }
+
+    #[test]
+    fn voter_validation_writes_are_billed_to_storage_balance() {
+        let (contract, _context) = voter_stake();
+        let min_balance = contract.storage_balance_bounds().min.0;
+        let storage_balance = contract.storage_balance_of(user2()).unwrap();
+        assert_eq!(storage_balance.total.0, 100_000 * STORAGE_PRICE_PER_BYTE);
+        assert!(storage_balance.available.0 < storage_balance.total.0 - min_balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough storage balance")]
+    fn voter_validation_write_above_storage_balance_fails() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        context.attached_deposit = contract.storage_balance_bounds().min.0;
+        testing_env!(context.clone());
+        contract.storage_deposit(Some(bob()), None);
+        contract.create_voter_profile("c1d1a89574c6e744d982e0f2bf1154ef05c13".to_owned());
+    }
+
+    #[test]
+    fn storage_unregister_with_balance_requires_force() {
+        let (mut contract, mut context) = ft_transfer_to_bob_for_test(100);
+        context.predecessor_account_id = bob();
+        context.attached_deposit = 1;
+        testing_env!(context.clone());
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            contract.storage_unregister(None);
+        }));
+        assert!(result.is_err());
+        testing_env!(context.clone());
+        let total_supply = contract.ft_total_supply().0;
+        assert!(contract.storage_unregister(Some(true)));
+        assert!(contract.storage_balance_of(bob()).is_none());
+        assert_eq!(contract.ft_balance_of(bob()).0, 0);
+        assert_eq!(contract.ft_total_supply().0, total_supply - 100);
+        assert!(!contract.storage_unregister(None));
+    }
+
+    #[test]
+    fn ft_metadata_is_set_at_init() {
+        let context = get_context(carol());
+        testing_env!(context);
+        let total_supply = 1_000_000_000_000_000u128;
+        let contract = FungibleToken::new_with_config(
+            carol(),
+            total_supply.into(),
+            FungibleTokenMetadata {
+                name: "Governance".to_owned(),
+                symbol: "GOV".to_owned(),
+                decimals: 2,
+                ..FungibleTokenMetadata::default()
+            },
+            ValidationConfig::default(),
+        );
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.spec, "ft-1.0.0");
+        assert_eq!(metadata.name, "Governance");
+        assert_eq!(metadata.symbol, "GOV");
+        assert_eq!(metadata.decimals, 2);
+        assert!(metadata.icon.is_none());
+    }
+
+    #[test]
+    fn owner_updates_icon_and_reference() {
+        let context = get_context(carol());
+        testing_env!(context);
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        contract.set_icon(Some("data:image/svg+xml,<svg></svg>".to_owned()));
+        contract.set_reference(
+            Some("https://example.com/shivarthu.json".to_owned()),
+            Some(vec![1; 32].into()),
+        );
+        let metadata = contract.ft_metadata();
+        assert_eq!(metadata.icon.unwrap(), "data:image/svg+xml,<svg></svg>");
+        assert_eq!(
+            metadata.reference.unwrap(),
+            "https://example.com/shivarthu.json"
+        );
+        assert_eq!(metadata.reference_hash.unwrap().0, vec![1; 32]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn set_icon_by_other_account_fails() {
+        let context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(bob(), total_supply.into());
+        contract.set_icon(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reference and reference hash should be set together")]
+    fn set_reference_without_hash_fails() {
+        let context = get_context(carol());
+        testing_env!(context);
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        contract.set_reference(Some("https://example.com/shivarthu.json".to_owned()), None);
+    }
+
+    #[test]
+    fn owner_transfers_ownership() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        assert_eq!(contract.get_owner(), carol());
+        contract.transfer_ownership(bob());
+        assert_eq!(contract.get_owner(), bob());
+        context.predecessor_account_id = bob();
+        testing_env!(context.clone());
+        contract.pause();
+        assert!(contract.is_paused());
+        context.predecessor_account_id = carol();
+        testing_env!(context.clone());
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| contract.unpause()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn pause_by_other_account_fails() {
+        let context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(bob(), total_supply.into());
+        contract.pause();
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract is paused")]
+    fn transfer_while_paused_fails() {
+        let mut context = get_context(carol());
+        testing_env!(context.clone());
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut contract = FungibleToken::new(carol(), total_supply.into());
+        contract.pause();
+        context.attached_deposit = 1000 * STORAGE_PRICE_PER_BYTE;
+        testing_env!(context.clone());
+        contract.transfer(bob(), 10.into());
+    }
+
+    #[test]
+    fn pause_blocks_voter_validation_but_not_views() {
+        let (mut contract, mut context) = juror_applications_for_test(test_validation_config());
+        context.block_timestamp = draw_phase_time();
+        testing_env!(context.clone());
+        contract.pause();
+        let bob_id = contract.get_user_id(&bob());
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::Draw
+        );
+        assert_eq!(contract.get_voter_stake(U128(bob_id)), 50);
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| contract.draw_jurors(bob())));
+        assert!(result.is_err());
+        contract.unpause();
+        contract.draw_jurors(bob());
+        assert_eq!(contract.get_selected_jurors(bob_id).len(), 6);
+    }
+
+    #[test]
+    fn pause_extends_phase_deadlines() {
+        let (mut contract, mut context) = draw_jurors_for_test();
+        let bob_id = contract.get_user_id(&bob());
+        let reveal_end = contract
+            .get_voter_application_timeline(U128(bob_id))
+            .reveal_end;
+        context.block_timestamp = commit_phase_time();
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.commit_juror_vote(bob(), vote_commit("juror1", "1juror1secret"));
+
+        // The contract is paused for the whole reveal window
+        context.block_timestamp = reveal_phase_time() + 1;
+        testing_env!(context.clone());
+        contract.pause();
+        context.block_timestamp = appeal_phase_time() + 1;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::Reveal
+        );
+        contract.unpause();
+        assert_eq!(
+            contract
+                .get_voter_application_timeline(U128(bob_id))
+                .reveal_end,
+            reveal_end + REVEAL_PERIOD
+        );
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::Reveal
+        );
+        contract.reveal_juror_vote(bob(), "1juror1secret".to_owned());
+        let juror1_id = contract.get_user_id(&"juror1".to_owned());
+        assert_eq!(contract.get_juror_vote(U128(bob_id), juror1_id), Some(true));
+
+        context.block_timestamp = appeal_phase_time() + REVEAL_PERIOD;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.get_voter_application_phase(U128(bob_id)),
+            ApplicationPhase::Appeal
+        );
+    }
+
+    /// Writes the state of the first deployed contract, with bob's voter application in
+    /// progress: bob staked 50, juror1 applied with 20 and was drawn, juror2 applied with 10
+    /// and wasn't drawn, and alice gave juror1 an allowance.
+    fn version_1_state_for_test() -> FungibleTokenV1 {
+        let total_supply = 1_000_000_000_000_000u128;
+        let mut state = FungibleTokenV1 {
+            accounts: UnorderedMap::new(b"31ec9a4c-af6f-44dd-a488-bfdc45c493a7".to_vec()),
+            total_supply: total_supply - 50 - 20 - 10,
+            user_id: 0,
+            user_map: LookupMap::new(b"2a543bc7-a03f-427f-98c4-aa34012fa358".to_vec()),
+            voter_profile_map: LookupMap::new(b"a9d08e6d-fe16-441e-9330-81f45b8a68b3".to_vec()),
+            voter_if_staked: LookupMap::new(b"0e9cdb00-e90a-4aed-8541-1fb2ea6a1538".to_vec()),
+            voter_stakes: LookupMap::new(b"de89b05f-e35d-4237-bba9-64b2baac1ca8".to_vec()),
+            user_juror_stakes: LookupMap::new(b"96a7bcb7-5c9e-4af5-b33c-eb7a4c3a38a1".to_vec()),
+            user_juror_stakes_clone: LookupMap::new(
+                b"aaabae72-8319-42f0-9e8b-19d5db7d176d".to_vec(),
+            ),
+            juror_stake_unique_id: 0,
+            selected_juror: LookupMap::new(b"827a829e-e9b9-4e85-908b-49f8dab0e496".to_vec()),
+        };
+        let balances = [
+            (alice(), total_supply - 300),
+            (bob(), 100 - 50),
+            ("juror1".to_owned(), 100 - 20),
+            ("juror2".to_owned(), 100 - 10),
+        ];
+        for (account_id, balance) in balances.iter() {
+            let account_hash = env::sha256(account_id.as_bytes());
+            let mut allowances = UnorderedMap::new(account_hash.clone());
+            if *account_id == alice() {
+                allowances.insert(&env::sha256(b"juror1"), &30);
+            }
+            let account = AccountV1 {
+                balance: *balance,
+                allowances,
+            };
+            state.accounts.insert(&account_hash, &account);
+        }
+        for account_id in [bob(), "juror1".to_owned(), "juror2".to_owned()].iter() {
+            state.user_id += 1;
+            state.user_map.insert(account_id, &state.user_id);
+            let voter = VoterV1 {
+                profile_hash: format!("{}profile", account_id),
+                kyc_done: state.user_id != 1,
+            };
+            state.voter_profile_map.insert(&state.user_id, &voter);
+        }
+        state.voter_if_staked.insert(&1, &true);
+        state.voter_stakes.insert(&1, &50);
+        let mut juror_stakes = LookupMap::new(b"stakevoterid1uniqueid0".to_vec());
+        juror_stakes.insert(&2, &20);
+        juror_stakes.insert(&3, &10);
+        state.user_juror_stakes.insert(&1, &juror_stakes);
+        // juror1 is removed from the clone when drawn
+        let mut juror_stakes_clone = TreeMap::new(b"stakevoteridclone1uniqueid0".to_vec());
+        juror_stakes_clone.insert(&3, &10);
+        state
+            .user_juror_stakes_clone
+            .insert(&1, &juror_stakes_clone);
+        state.juror_stake_unique_id += 1;
+        let mut jurors = LookupSet::new(b"jurysetidbob.near".to_vec());
+        jurors.insert(&2);
+        state.selected_juror.insert(&1, &jurors);
+        env::storage_write(b"STATE", &state.try_to_vec().unwrap());
+        state
+    }
+
+    #[test]
+    fn migrate_converts_version_1_state_in_steps() {
+        let mut context = get_context(alice());
+        testing_env!(context.clone());
+        version_1_state_for_test();
+        assert_eq!(VersionedFungibleToken::read().version(), 1);
+
+        let mut contract = FungibleToken::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.get_owner(), alice());
+        assert!(contract.is_migrating());
+        contract.add_migrated_accounts(vec!["juror2".to_owned(), "juror1".to_owned()]);
+        contract.add_migrated_accounts(vec![bob()]);
+        // 4 accounts and 3 users
+        let mut steps = 1;
+        while !contract.migrate_step(2) {
+            steps += 1;
+        }
+        assert_eq!(steps, 4);
+        assert!(!contract.is_migrating());
+        assert_eq!(contract.get_user_account_id(1), bob());
+        assert_eq!(contract.get_user_account_id(3), "juror2".to_owned());
+        // The burned stakes of bob and juror2, who wasn't drawn, are minted back
+        let total_supply = 1_000_000_000_000_000u128;
+        assert_eq!(contract.get_total_supply().0, total_supply - 20);
+        assert_eq!(contract.get_balance(bob()).0, 100);
+        assert_eq!(contract.get_balance("juror2".to_owned()).0, 100);
+        assert_eq!(contract.get_balance("juror1".to_owned()).0, 80);
+        assert_eq!(contract.get_locked_balance(bob()).0, 0);
+        assert_eq!(contract.get_allowance(alice(), "juror1".to_owned()).0, 30);
+        assert_eq!(
+            contract.get_voter_application_phase(U128(1)),
+            ApplicationPhase::NotStarted
+        );
+        assert_eq!(
+            contract.get_voter_details(1).profile_hash,
+            "bob.nearprofile"
+        );
+        assert!(!contract.is_validated_voter(bob()));
+        assert!(contract.is_validated_voter("juror1".to_owned()));
+        let juror_stakes_clone: TreeMap<u128, u128> =
+            TreeMap::new(b"stakevoteridclone1uniqueid0".to_vec());
+        assert!(juror_stakes_clone.get(&3).is_none());
+
+        // juror1 was drawn and claims its stake
+        context.signer_account_id = "juror1".to_owned();
+        testing_env!(context.clone());
+        contract.claim_version_1_juror_stake(1);
+        assert_eq!(contract.get_balance("juror1".to_owned()).0, 100);
+        assert_eq!(contract.get_total_supply().0, total_supply);
+        let jurors: LookupSet<u128> = LookupSet::new(b"jurysetidbob.near".to_vec());
+        assert!(!jurors.contains(&2));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            contract.claim_version_1_juror_stake(1)
+        }));
+        assert!(result.is_err());
+        env::state_write(&contract);
+        assert_eq!(VersionedFungibleToken::read().version(), STATE_VERSION);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract state is being migrated")]
+    fn transfer_while_migrating_fails() {
+        let context = get_context(alice());
+        testing_env!(context);
+        version_1_state_for_test();
+        let mut contract = FungibleToken::migrate();
+        contract.transfer(bob(), 10.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "Accounts of all the 3 users are needed")]
+    fn migrate_step_without_all_accounts_fails() {
+        let context = get_context(alice());
+        testing_env!(context);
+        version_1_state_for_test();
+        let mut contract = FungibleToken::migrate();
+        contract.add_migrated_accounts(vec![bob()]);
+        contract.migrate_step(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract state is already at version 2")]
+    fn migrate_current_state_fails() {
+        let context = get_context(carol());
+        testing_env!(context);
+        let total_supply = 1_000_000_000_000_000u128;
+        let contract = FungibleToken::new(carol(), total_supply.into());
+        env::state_write(&contract);
+        FungibleToken::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn migrate_by_other_account_fails() {
+        let context = get_context(carol());
+        testing_env!(context);
+        version_1_state_for_test();
+        FungibleToken::migrate();
+    }
 }
@@ -1,9 +1,22 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap, TreeMap, Vector, LookupSet};
-use near_sdk::{near_bindgen, wee_alloc, Balance};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, wee_alloc, AccountId, Balance, StorageUsage};
+use sortition_sum_tree::SortitionSumTree;
 
 mod account;
 use self::account::Account;
+pub mod events;
+mod metadata;
+pub use self::metadata::{FungibleTokenMetadata, FT_METADATA_SPEC};
+mod migration;
+pub use self::migration::{
+    AccountV1, FungibleTokenV1, JurorStakesV1, MigrationV1, VersionedFungibleToken, VoterV1,
+    STATE_VERSION,
+};
+mod reward;
+pub use self::reward::VoterRewardCurve;
 mod token;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -11,6 +24,26 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 /// Price per 1 byte of storage from mainnet genesis config.
 pub const STORAGE_PRICE_PER_BYTE: Balance = 100000000000000000000;
 
+/// Incentive minted for a voter application with a ruling, shared equally by the jurors who
+/// voted with the final ruling.
+pub const JUROR_INCENTIVE: Balance = 5;
+
+/// Number of children per node of the sum trees of juror stakes.
+pub const JUROR_STAKE_TREE_K: u128 = 4;
+
+/// Length of the phases of a voter application in nanoseconds, the unit of the block timestamp.
+pub const JUROR_APPLICATION_PERIOD: u64 = 3 * 24 * 60 * 60 * 1_000_000_000;
+pub const DRAW_PERIOD: u64 = 24 * 60 * 60 * 1_000_000_000;
+pub const COMMIT_PERIOD: u64 = 2 * 24 * 60 * 60 * 1_000_000_000;
+pub const REVEAL_PERIOD: u64 = 2 * 24 * 60 * 60 * 1_000_000_000;
+pub const APPEAL_PERIOD: u64 = 2 * 24 * 60 * 60 * 1_000_000_000;
+/// Default `reciprocal_jury_cooldown` of the validation config.
+pub const RECIPROCAL_JURY_COOLDOWN: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+/// Expertise application ids are counted from this value, above the user ids, as the voter
+/// and the expertise applications are kept in the same maps.
+pub const EXPERTISE_APPLICATION_ID_START: u128 = 1 << 64;
+
 /// Contains balance and allowances information for one account.
 ///
 
@@ -26,16 +59,242 @@ pub const STORAGE_PRICE_PER_BYTE: Balance = 100000000000000000000;
 #[derive(Debug, Default, BorshDeserialize, BorshSerialize)]
 pub struct Voter {
     pub profile_hash: String, //IPFS Hash
-    pub kyc_done: bool,
+    pub kyc_done: bool,       // Set when the jurors accept the voter
+    pub kyc_rejected: bool,   // Set when the jurors reject the voter
+}
+
+/// IPFS hash of a voter profile and the block timestamp when it was set.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProfileHashRecord {
+    pub profile_hash: String,
+    pub timestamp: u64,
+}
+
+/// Settings of the voter validation that can be chosen at initialization.
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ValidationConfig {
+    /// Percentage of the juror applicants drawn for a voter application.
+    pub jury_size_percent: u64,
+    /// Minimum number of jurors drawn, the draw is refused if fewer jurors have applied.
+    pub min_jurors: u64,
+    /// Reward paid on the stake of a voter whose application is accepted.
+    pub voter_reward_curve: VoterRewardCurve,
+    /// Fee paid to appeal the ruling of a round, returned if the final ruling is changed.
+    pub appeal_fee: U128,
+    /// Multiplies the stake of a juror applicant by its reputation weight in the draw. The
+    /// weight is taken from the reputation the juror has when applying.
+    pub reputation_weighted_draw: bool,
+    /// Time after a juror is drawn for a voter during which the voter can't be a juror
+    /// for that juror, 0 to allow reciprocal jurying.
+    pub reciprocal_jury_cooldown: u64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            jury_size_percent: 50,
+            min_jurors: 10,
+            voter_reward_curve: VoterRewardCurve::default(),
+            appeal_fee: 10.into(),
+            reputation_weighted_draw: false,
+            reciprocal_jury_cooldown: RECIPROCAL_JURY_COOLDOWN,
+        }
+    }
+}
+
+/// Block timestamp deadlines of a voter application, set when the voter stakes.
+/// An appeal moves the draw, commit, reveal and appeal deadlines for the new round.
+/// A pause of the contract moves all the deadlines by the length of the pause.
+#[derive(Debug, Default, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoterApplicationTimeline {
+    /// Jurors can apply till this time.
+    pub juror_application_end: u64,
+    /// Jurors can be drawn from `juror_application_end` till this time.
+    pub draw_end: u64,
+    /// Selected jurors can commit their vote from `draw_end` till this time.
+    pub commit_end: u64,
+    /// Selected jurors can reveal their vote from `commit_end` till this time.
+    pub reveal_end: u64,
+    /// The ruling can be appealed from `reveal_end` till this time,
+    /// after which the application can be settled.
+    pub appeal_end: u64,
 }
 
+/// Status of a juror application, in the order it moves through a voter application.
+#[derive(Debug, Clone, Copy, PartialEq, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum JurorApplicationStatus {
+    Applied,
+    Drawn,
+    Voted,
+    Revealed,
+    Withdrawn,
+    Settled,
+}
+
+/// Application of a juror for a voter, holding the stake and the vote of the juror.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JurorApplication {
+    pub stake: U128,
+    pub status: JurorApplicationStatus,
+    /// Round the juror is drawn in, None if the juror is not drawn.
+    pub round: Option<u64>,
+    pub vote_commit: Option<String>,
+    /// Revealed vote, `true` for accept and `false` for reject.
+    pub vote: Option<bool>,
+}
+
+/// How a juror voted in the settled voter applications it was drawn for.
+#[derive(Debug, Default, PartialEq, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JurorReputation {
+    pub rounds_drawn: u64,
+    pub rounds_revealed: u64,
+    /// Votes that matched the final ruling.
+    pub coherent_votes: u64,
+    /// Stakes lost for voting against the final ruling or not revealing the vote.
+    pub slashes: u64,
+}
+
+impl JurorReputation {
+    /// Percentage the stake is multiplied by in a reputation weighted draw, from 50 for a juror
+    /// who was never coherent to 150 for a juror who always was. The rounds without a ruling
+    /// in which the juror revealed its vote are neither coherent nor slashed, so they are
+    /// left out. Jurors without such rounds get 100.
+    pub fn draw_weight_percent(&self) -> u128 {
+        let judged_rounds = self.coherent_votes + self.slashes;
+        if judged_rounds == 0 {
+            100
+        } else {
+            50 + u128::from(self.coherent_votes) * 100 / u128::from(judged_rounds)
+        }
+    }
+}
+
+/// Juror application along with the user ids and account ids of the voter and the juror,
+/// returned by the paginated views.
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JurorApplicationView {
+    pub voter_user_id: U128,
+    pub voter_account_id: AccountId,
+    pub juror_user_id: U128,
+    pub juror_account_id: AccountId,
+    pub application: JurorApplication,
+}
+
+/// Appeal against the ruling of a round of a voter application, which starts a new round
+/// with a larger jury.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Appeal {
+    pub appellant: u128, // user_id of the voter or the juror who appealed
+    pub fee: U128,
+    /// Ruling of the appealed round, None if the round gave no ruling.
+    pub appealed_ruling: Option<bool>,
+}
+
+/// Bond a validated voter locked to vouch for a voter application, returned if the voter
+/// is accepted and slashed if the voter is rejected.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vouch {
+    pub voucher: u128, // user_id of the validated voter who vouched
+    pub bond: U128,
+}
+
+/// Claim of a user to be an expert of a department. It is validated like a voter
+/// application, keyed by its own application id, by jurors from the experts of the department.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExpertiseApplication {
+    pub user_id: u128, // user_id of the claimant
+    pub department: String,
+    pub profile_hash: String, // IPFS hash of the proof of expertise
+}
+
+/// Inputs and draws of the juror draw of a round, which the draw-verifier crate recomputes
+/// from the seed to prove the jury was drawn fairly.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DrawTranscript {
+    pub round: u64,
+    /// `env::random_seed()` of the block the jurors were drawn in.
+    pub seed: Vec<u8>,
+    /// User ids of the juror applicants in the stake tree before the draw, in the order the
+    /// sum tree draw goes through them.
+    pub candidates: Vec<U128>,
+    /// Draw weights of the candidates, in the same order.
+    pub weights: Vec<U128>,
+    /// Draws in the order they were made.
+    pub draws: Vec<JurorDraw>,
+    /// Account that drew the jurors and paid for the storage of the round.
+    pub drawn_by: AccountId,
+}
+
+/// Final ruling of a settled voter application.
 #[derive(BorshDeserialize, BorshSerialize)]
-pub struct SortitionSumTree {
-    k: u128,
-    stack: Vector<u128>,
-    nodes: Vector<u128>,
-    ids_to_node_indexes: TreeMap<String, u128>,
-    node_indexes_to_ids: TreeMap<u128, String>,
+pub struct VoterRuling {
+    pub accepted: bool,
+    /// Account that settled the application and paid for the storage of the ruling.
+    pub settled_by: AccountId,
+}
+
+/// One draw of a juror from the stake tree.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JurorDraw {
+    /// Number generated from the seed, which draws the juror whose weight range holds it
+    /// modulo the total weight left in the tree.
+    pub draw_number: U128,
+    pub juror_user_id: U128,
+    /// Draw weight of the juror, which is removed from the tree once drawn.
+    pub weight: U128,
+    /// The juror got a conflict of interest with the voter after applying, so it is withdrawn
+    /// instead of joining the jury.
+    pub withdrawn: bool,
+}
+
+/// Storage paid by a registered account, the registration itself isn't part of `used`.
+#[derive(Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct AccountStorage {
+    /// Deposit attached with `storage_deposit`, less the withdrawn amount.
+    pub deposit: Balance,
+    /// Bytes of state written by the voter validation calls of the account.
+    pub used: StorageUsage,
+}
+
+/// NEP-145 storage balance of an account.
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+/// NEP-145 bounds of the storage balance, there is no maximum.
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ApplicationPhase {
+    NotStarted,
+    JurorApplication,
+    Draw,
+    Commit,
+    Reveal,
+    Appeal,
+    Settlement,
+    Settled,
 }
 
 #[near_bindgen]
@@ -47,18 +306,59 @@ pub struct FungibleToken {
     /// Total supply of the all token.
     total_supply: Balance,
 
+    /// Account that can update the metadata, manage the departments and pause the contract.
+    owner_id: AccountId,
+    /// While paused, transfers, storage management and voter validation are blocked,
+    /// views and the owner methods still work.
+    paused: bool,
+    /// Block timestamp the current pause started at.
+    paused_at: u64,
+    /// Time the contract was paused for, the phase deadlines of the applications are
+    /// extended by it.
+    paused_duration: u64,
+    metadata: FungibleTokenMetadata,
+
+    /// Storage deposits of the registered accounts.
+    account_storage: LookupMap<AccountId, AccountStorage>,
+    /// Bytes of storage used to register an account, paid by the minimum storage balance.
+    account_storage_usage: StorageUsage,
+
     // Voter validation
+    validation_config: ValidationConfig,
     user_id: u128,
     user_map: LookupMap<String, u128>, // <Account_name, user_id>
+    user_account_map: LookupMap<u128, String>, // <user_id, Account_name>
     voter_profile_map: LookupMap<u128, Voter>, // <user_id, Voter>
+    voter_profile_history: LookupMap<u128, Vector<ProfileHashRecord>>, // <user_id, profile hashes>
     voter_if_staked: LookupMap<u128, bool>, // <user_id, true or false>
     voter_stakes: LookupMap<u128, u128>, // <user_id, stakes>
-    // juror_stakes: LookupMap<u128, LookupMap<u128, u128>>, //<juror user_id, <voter userid, stakes>>
-    // juror_if_staked: LookupMap<u128, Vector<LookupMap<u128, u128>>>, // <juror user_id, <voter_user_id, true or false>>
-    // juror_applied_for: LookupMap<u128, LookupSet<u128>>, //<juror user_id, voter user id set>
-    user_juror_stakes: LookupMap<u128, LookupMap<u128, u128>>, // <voter_user_id, <jurorid, stakes>>
-    user_juror_stakes_clone: LookupMap<u128, TreeMap<u128, u128>>,
+    voter_timeline: LookupMap<u128, VoterApplicationTimeline>, // <user_id, timeline>
+    juror_applied_for: LookupMap<u128, UnorderedSet<u128>>, // <juror user_id, voter user id set>
+    juror_applications: LookupMap<u128, LookupMap<u128, JurorApplication>>, // <voter_user_id, <jurorid, application>>
+    juror_applicants: LookupMap<u128, Vector<u128>>, // <voter_user_id, jurorids in the order they applied>
+    juror_stake_trees: LookupMap<u128, SortitionSumTree>, // <voter_user_id, stakes of jurors not drawn yet>
     juror_stake_unique_id: u128,
-    selected_juror: LookupMap<u128, LookupSet<u128>> // <voter_user_id, jurorid>
+    selected_juror: LookupMap<u128, Vector<UnorderedSet<u128>>>, // <voter_user_id, jurorids of each round>
+    voter_appeals: LookupMap<u128, Vector<Appeal>>, // <voter_user_id, appeals>
+    juror_vote_unique_id: u128,
+    voter_ruling: LookupMap<u128, VoterRuling>, // <voter_user_id, ruling> set on settlement
+    juror_reputation: LookupMap<u128, JurorReputation>, // <juror user_id, reputation> updated on settlement
+    draw_transcripts: LookupMap<u128, Vector<DrawTranscript>>, // <voter_user_id, draw transcript of each round>
+    jury_service: LookupMap<u128, LookupMap<u128, u64>>, // <juror user_id, <voter user_id, time of the last draw>>
+    declared_relationships: LookupMap<u128, UnorderedSet<u128>>, // <user_id, user ids of the related accounts>
+    voter_vouches: LookupMap<u128, Vector<Vouch>>, // <voter_user_id, vouches of validated voters>
+
+    // Expert departments, an expertise application id is counted from
+    // EXPERTISE_APPLICATION_ID_START so that the maps of the voter applications above hold
+    // the expertise applications too
+    departments: UnorderedSet<String>,
+    department_experts: LookupMap<String, UnorderedSet<u128>>, // <department, expert user ids>
+    expertise_applications: LookupMap<u128, ExpertiseApplication>, // <application id, claim>
+    expertise_claims: LookupMap<u128, UnorderedMap<String, u128>>, // <user_id, <department, latest application id>>
+    expertise_application_id: u128, // number of expertise applications
+
+    // Migration of the version 1 state
+    migration: Option<MigrationV1>, // progress, till `migrate_step` completes it
+    version_1_juror_stakes: LookupMap<u128, JurorStakesV1>, // <voter_user_id, stakes of the drawn jurors>
 }
 
@@ -8,6 +8,8 @@ use near_sdk::{env, AccountId, Balance};
 pub struct Account {
     /// Current account balance.
     pub balance: Balance,
+    /// Balance staked in voter validation, which can't be spent until the stake is released.
+    pub locked_balance: Balance,
     /// Escrow Account ID hash to the allowance amount.
     /// Allowance is the amount of tokens the Escrow Account ID can spent on behalf of the account
     /// owner.
@@ -19,6 +21,7 @@ impl Account {
     pub fn new(account_hash: Vec<u8>) -> Self {
         Self {
             balance: 0,
+            locked_balance: 0,
             allowances: UnorderedMap::new(account_hash),
         }
     }
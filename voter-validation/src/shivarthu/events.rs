@@ -0,0 +1,204 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, serde_json, AccountId};
+
+/// Standard name and version of the NEP-297 event logs of the contract.
+pub const EVENT_STANDARD: &str = "shivarthu";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Standard name and version of the NEP-141 token event logs.
+pub const FT_EVENT_STANDARD: &str = "nep141";
+pub const FT_EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Prefix of a log line that holds a NEP-297 event.
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// Balance changes of the fungible token, logged as NEP-141 events so that wallets and
+/// indexers pick them up. Each event holds a list of changes.
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum FtEvent {
+    FtMint(Vec<FtMint>),
+    FtBurn(Vec<FtBurn>),
+    FtTransfer(Vec<FtTransfer>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMint {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtBurn {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtTransfer {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub amount: U128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// State changes of the contract other than the token balances, logged as NEP-297 events.
+/// User ids and amounts are strings in JSON, like the views.
+#[derive(Debug, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    /// Allowance of `escrow_account_id` on the account of `owner_id` is set to `allowance`.
+    AllowanceChange {
+        owner_id: AccountId,
+        escrow_account_id: AccountId,
+        allowance: U128,
+    },
+    OwnershipTransfer {
+        old_owner_id: AccountId,
+        new_owner_id: AccountId,
+    },
+    /// The owner paused the contract, or unpaused it.
+    ContractPause { paused: bool },
+    /// `migrate` converted the contract state from `from_version` to `to_version`.
+    StateMigrate { from_version: u32, to_version: u32 },
+    /// Icon or reference of the metadata is updated by the owner.
+    FtMetadataUpdate,
+    VoterProfileCreate {
+        user_id: U128,
+        account_id: AccountId,
+        profile_hash: String,
+    },
+    VoterProfileUpdate {
+        user_id: U128,
+        account_id: AccountId,
+        profile_hash: String,
+    },
+    VoterStake {
+        voter_user_id: U128,
+        stake: U128,
+        juror_application_end: u64,
+    },
+    /// `user_id` declared a relationship with `related_user_id`, neither can be a juror
+    /// for the other.
+    RelationshipDeclare {
+        user_id: U128,
+        related_user_id: U128,
+    },
+    /// `voucher` locked `bond` to vouch for the voter application.
+    VoterVouch {
+        voter_user_id: U128,
+        voucher: U128,
+        bond: U128,
+    },
+    /// The owner added a department.
+    DepartmentAdd { department: String },
+    /// The user became an expert of the department, seeded by the owner or accepted by
+    /// the experts of the department.
+    DepartmentExpertAdd { department: String, user_id: U128 },
+    /// The expertise of the user was revoked because its voter profile was reset.
+    DepartmentExpertRemove { department: String, user_id: U128 },
+    /// The user claimed expertise in the department, the claim is validated as the
+    /// application `application_id`.
+    ExpertiseClaim {
+        application_id: U128,
+        user_id: U128,
+        department: String,
+        profile_hash: String,
+    },
+    JurorApply {
+        voter_user_id: U128,
+        juror_user_id: U128,
+        stake: U128,
+    },
+    JurorWithdraw {
+        voter_user_id: U128,
+        juror_user_id: U128,
+        stake: U128,
+    },
+    JurorDraw {
+        voter_user_id: U128,
+        round: u64,
+        juror_user_ids: Vec<U128>,
+    },
+    VoteCommit {
+        voter_user_id: U128,
+        juror_user_id: U128,
+        round: u64,
+    },
+    VoteReveal {
+        voter_user_id: U128,
+        juror_user_id: U128,
+        round: u64,
+        vote: bool,
+    },
+    VoterApplicationAppeal {
+        voter_user_id: U128,
+        appellant: U128,
+        round: u64,
+        fee: U128,
+    },
+    /// No jury was drawn for the voter application, the stakes are returned and the
+    /// application is cleared.
+    VoterApplicationCancel { voter_user_id: U128 },
+    /// `accepted` is None if the last round gave no ruling, the stakes are returned and
+    /// the application is cleared.
+    VoterApplicationSettle {
+        voter_user_id: U128,
+        accepted: Option<bool>,
+        rounds: u64,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, E: Serialize> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a E,
+}
+
+/// Returns the log line of the event, `EVENT_JSON:` followed by the event as JSON.
+fn to_log_string<E: Serialize>(standard: &'static str, version: &'static str, event: &E) -> String {
+    let log = EventLog {
+        standard,
+        version,
+        event,
+    };
+    format!(
+        "{}{}",
+        EVENT_JSON_PREFIX,
+        serde_json::to_string(&log).unwrap()
+    )
+}
+
+impl Event {
+    pub fn to_log_string(&self) -> String {
+        to_log_string(EVENT_STANDARD, EVENT_STANDARD_VERSION, self)
+    }
+
+    pub fn emit(&self) {
+        env::log(self.to_log_string().as_bytes());
+    }
+}
+
+impl FtEvent {
+    pub fn to_log_string(&self) -> String {
+        to_log_string(FT_EVENT_STANDARD, FT_EVENT_STANDARD_VERSION, self)
+    }
+
+    pub fn emit(&self) {
+        env::log(self.to_log_string().as_bytes());
+    }
+}
@@ -0,0 +1,59 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Version of the NEP-148 metadata standard.
+pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
+
+/// NEP-148 metadata of the token, shown by wallets.
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FungibleTokenMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    /// Data URL of the icon.
+    pub icon: Option<String>,
+    /// Link to a JSON file with more information about the token.
+    pub reference: Option<String>,
+    /// Base64 encoded sha256 hash of the JSON file of `reference`.
+    pub reference_hash: Option<Base64VecU8>,
+    pub decimals: u8,
+}
+
+impl Default for FungibleTokenMetadata {
+    /// Amounts are whole tokens in the voter validation, so the token has no decimals.
+    fn default() -> Self {
+        Self {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Shivarthu".to_string(),
+            symbol: "SHIV".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 0,
+        }
+    }
+}
+
+impl FungibleTokenMetadata {
+    pub fn assert_valid(&self) {
+        assert_eq!(
+            self.spec, FT_METADATA_SPEC,
+            "Metadata spec should be {}",
+            FT_METADATA_SPEC
+        );
+        assert_eq!(
+            self.reference.is_some(),
+            self.reference_hash.is_some(),
+            "Reference and reference hash should be set together"
+        );
+        if let Some(reference_hash) = &self.reference_hash {
+            assert_eq!(
+                reference_hash.0.len(),
+                32,
+                "Reference hash should be 32 bytes"
+            );
+        }
+    }
+}
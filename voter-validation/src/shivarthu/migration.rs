@@ -0,0 +1,129 @@
+use super::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap};
+use near_sdk::{env, AccountId, Balance};
+
+/// Version of the current layout of `FungibleToken`.
+pub const STATE_VERSION: u32 = 2;
+
+/// Storage key of the contract state, written by near-sdk.
+const STATE_KEY: &[u8] = b"STATE";
+
+/// Storage key of the version of the layout of the contract state. Version 1 was deployed
+/// before the key existed, so a state without it is in the version 1 layout.
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// Account of the version 1 layout, which has no locked balance.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountV1 {
+    pub balance: Balance,
+    pub allowances: UnorderedMap<Vec<u8>, Balance>,
+}
+
+/// Voter of the version 1 layout, which has no rejection by the jurors.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VoterV1 {
+    pub profile_hash: String,
+    pub kyc_done: bool,
+}
+
+/// Layout of the first deployed contract state. The stakes of the voters and the jurors
+/// were burned, and the jurors of a voter were drawn without any deadline.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FungibleTokenV1 {
+    pub accounts: UnorderedMap<Vec<u8>, AccountV1>,
+    pub total_supply: Balance,
+    pub user_id: u128,
+    pub user_map: LookupMap<String, u128>, // <Account_name, user_id>
+    pub voter_profile_map: LookupMap<u128, VoterV1>, // <user_id, Voter>
+    pub voter_if_staked: LookupMap<u128, bool>, // <user_id, true or false>
+    pub voter_stakes: LookupMap<u128, u128>, // <user_id, stakes>
+    pub user_juror_stakes: LookupMap<u128, LookupMap<u128, u128>>, // <voter_user_id, <jurorid, stakes>>
+    pub user_juror_stakes_clone: LookupMap<u128, TreeMap<u128, u128>>,
+    pub juror_stake_unique_id: u128,
+    pub selected_juror: LookupMap<u128, LookupSet<u128>>, // <voter_user_id, jurorid>
+}
+
+/// Progress of the migration of the version 1 state, which `migrate_step` does in batches so
+/// that each call fits in the gas limit.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MigrationV1 {
+    /// Version 1 state with the collections that are still migrated.
+    pub state: FungibleTokenV1,
+    /// Index in the values of `accounts` of the next account to re-encode.
+    pub next_account_index: u64,
+    /// Number of users whose account is added with `add_migrated_accounts`.
+    pub added_accounts: u128,
+    /// Next user id whose voter profile and application are migrated.
+    pub next_user_id: u128,
+}
+
+/// Stakes of the jurors drawn for a version 1 application. The drawn jurors can't be listed
+/// on chain, so each of them claims its stake with `claim_version_1_juror_stake`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct JurorStakesV1 {
+    pub stakes: LookupMap<u128, u128>, // <jurorid, stakes>
+    pub drawn_jurors: LookupSet<u128>,
+}
+
+/// Contract state in each of its layouts. To change the layout of `FungibleToken`, copy its
+/// current fields into a new `FungibleTokenV<n>` struct with a variant here, convert the
+/// variant in `migrate` and increment `STATE_VERSION`.
+pub enum VersionedFungibleToken {
+    V1(Box<FungibleTokenV1>),
+    V2(Box<FungibleToken>),
+}
+
+impl VersionedFungibleToken {
+    /// Reads the contract state in the layout of its stored version.
+    pub fn read() -> Self {
+        let data = match env::storage_read(STATE_KEY) {
+            Some(data) => data,
+            None => panic!("The contract is not initialized"),
+        };
+        match Self::stored_version() {
+            1 => match FungibleTokenV1::try_from_slice(&data) {
+                Ok(state) => VersionedFungibleToken::V1(Box::new(state)),
+                Err(_) => panic!("Cannot deserialize the contract state of version 1"),
+            },
+            STATE_VERSION => match FungibleToken::try_from_slice(&data) {
+                Ok(state) => VersionedFungibleToken::V2(Box::new(state)),
+                Err(_) => panic!("Cannot deserialize the contract state of version 2"),
+            },
+            version => panic!("Unknown contract state version {}", version),
+        }
+    }
+
+    /// Returns the version of the layout the contract state is written in.
+    pub fn stored_version() -> u32 {
+        match env::storage_read(STATE_VERSION_KEY) {
+            Some(version) => match u32::try_from_slice(&version) {
+                Ok(version) => version,
+                Err(_) => panic!("Cannot deserialize the contract state version"),
+            },
+            None => 1,
+        }
+    }
+
+    /// Stores `STATE_VERSION` as the version of the contract state, when the state is
+    /// initialized or migrated.
+    pub fn write_current_version() {
+        env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            VersionedFungibleToken::V1(_) => 1,
+            VersionedFungibleToken::V2(_) => STATE_VERSION,
+        }
+    }
+
+    /// Returns the account that can migrate the state. Version 1 has no owner, so the
+    /// contract account itself migrates it.
+    pub fn owner_id(&self) -> AccountId {
+        match self {
+            VersionedFungibleToken::V1(_) => env::current_account_id(),
+            VersionedFungibleToken::V2(state) => state.get_owner(),
+        }
+    }
+}
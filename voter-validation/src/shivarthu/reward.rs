@@ -0,0 +1,54 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Balance;
+
+/// Reward curve for the stake of a voter whose application is accepted.
+/// The voter earns back the stake along with the reward.
+#[derive(Debug, Clone, Default, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VoterRewardCurve {
+    /// Reward is the square root of the stake, so staking `n * n` tokens earns `n` tokens.
+    #[default]
+    Quadratic,
+    /// Reward is `fixed_reward` if the stake is more than `threshold`, else 50% of the stake.
+    Step { threshold: U128, fixed_reward: U128 },
+}
+
+impl VoterRewardCurve {
+    /// Returns the reward for `stake`.
+    pub fn reward(&self, stake: Balance) -> Balance {
+        match self {
+            VoterRewardCurve::Quadratic => integer_sqrt(stake),
+            VoterRewardCurve::Step {
+                threshold,
+                fixed_reward,
+            } => {
+                if stake > threshold.0 {
+                    fixed_reward.0
+                } else {
+                    stake / 2
+                }
+            }
+        }
+    }
+}
+
+/// Largest integer whose square is less than or equal to `value`, using Newton's method.
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = average(x, value / x);
+    while y < x {
+        x = y;
+        y = average(x, value / x);
+    }
+    x
+}
+
+/// Rounded down average of `x` and `y` without overflowing.
+fn average(x: u128, y: u128) -> u128 {
+    x / 2 + y / 2 + (x % 2 + y % 2) / 2
+}
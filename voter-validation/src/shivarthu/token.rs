@@ -1,31 +1,57 @@
+mod expertise;
+mod fungible_token;
+mod metadata;
+mod migration;
+mod owner;
+mod storage;
 mod votervalidation;
-use super::{FungibleToken, STORAGE_PRICE_PER_BYTE};
-use near_sdk::collections::{LookupMap, UnorderedMap};
+use super::{
+    AccountStorage, FungibleToken, FungibleTokenMetadata, ValidationConfig,
+    VersionedFungibleToken, STORAGE_PRICE_PER_BYTE,
+};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::U128;
 use near_sdk::{env, near_bindgen, AccountId, Balance, Promise, StorageUsage};
 use crate::shivarthu::account::Account;
+use crate::shivarthu::events::{Event, FtBurn, FtEvent, FtMint, FtTransfer};
 
 #[near_bindgen]
 impl FungibleToken {
     /// Initializes the contract with the given total supply owned by the given `owner_id`.
     #[init]
     pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        Self::new_with_config(
+            owner_id,
+            total_supply,
+            FungibleTokenMetadata::default(),
+            ValidationConfig::default(),
+        )
+    }
+
+    /// Initializes the contract with the given total supply owned by the given `owner_id`,
+    /// the given token metadata and the given voter validation settings.
+    #[init]
+    pub fn new_with_config(
+        owner_id: AccountId,
+        total_supply: U128,
+        metadata: FungibleTokenMetadata,
+        validation_config: ValidationConfig,
+    ) -> Self {
         let total_supply = total_supply.into();
         assert!(!env::state_exists(), "Already initialized");
-        let mut ft = Self {
-            accounts: UnorderedMap::new(b"31ec9a4c-af6f-44dd-a488-bfdc45c493a7".to_vec()),
-            total_supply,
-            user_id: 0,
-            user_map: LookupMap::new(b"2a543bc7-a03f-427f-98c4-aa34012fa358".to_vec()),
-            voter_profile_map: LookupMap::new(b"a9d08e6d-fe16-441e-9330-81f45b8a68b3".to_vec()),
-            voter_if_staked: LookupMap::new(b"0e9cdb00-e90a-4aed-8541-1fb2ea6a1538".to_vec()),
-            voter_stakes: LookupMap::new(b"de89b05f-e35d-4237-bba9-64b2baac1ca8".to_vec()),
-            // juror_stakes: LookupMap::new(b"bd08db59-eb71-489e-8cf8-a361a7e7fb39".to_vec()),
-            user_juror_stakes: LookupMap::new(b"96a7bcb7-5c9e-4af5-b33c-eb7a4c3a38a1".to_vec()),
-            user_juror_stakes_clone: LookupMap::new(b"aaabae72-8319-42f0-9e8b-19d5db7d176d".to_vec()),
-            juror_stake_unique_id: 0,
-            selected_juror: LookupMap::new(b"827a829e-e9b9-4e85-908b-49f8dab0e496".to_vec()),
-        };
+        metadata.assert_valid();
+        assert!(
+            validation_config.jury_size_percent > 0 && validation_config.jury_size_percent <= 100,
+            "Jury size percent should be between 1 and 100"
+        );
+        assert!(
+            validation_config.min_jurors > 0,
+            "Minimum jurors should be at least 1"
+        );
+        let mut ft =
+            Self::internal_new(owner_id.clone(), total_supply, metadata, validation_config);
+        ft.register_owner();
+        VersionedFungibleToken::write_current_version();
         let mut account = ft.get_account(&owner_id);
         account.balance = total_supply;
         ft.set_account(&owner_id, &account);
@@ -39,6 +65,7 @@ impl FungibleToken {
     ///   fixed storage price defined in the contract.
     #[payable]
     pub fn inc_allowance(&mut self, escrow_account_id: AccountId, amount: U128) {
+        self.assert_not_paused();
         let initial_storage = env::storage_usage();
         assert!(
             env::is_valid_account_id(escrow_account_id.as_bytes()),
@@ -50,11 +77,15 @@ impl FungibleToken {
         }
         let mut account = self.get_account(&owner_id);
         let current_allowance = account.get_allowance(&escrow_account_id);
-        account.set_allowance(
-            &escrow_account_id,
-            current_allowance.saturating_add(amount.0),
-        );
+        let allowance = current_allowance.saturating_add(amount.0);
+        account.set_allowance(&escrow_account_id, allowance);
         self.set_account(&owner_id, &account);
+        Event::AllowanceChange {
+            owner_id,
+            escrow_account_id,
+            allowance: allowance.into(),
+        }
+        .emit();
         self.refund_storage(initial_storage);
     }
 
@@ -65,6 +96,7 @@ impl FungibleToken {
     ///   fixed storage price defined in the contract.
     #[payable]
     pub fn dec_allowance(&mut self, escrow_account_id: AccountId, amount: U128) {
+        self.assert_not_paused();
         let initial_storage = env::storage_usage();
         assert!(
             env::is_valid_account_id(escrow_account_id.as_bytes()),
@@ -76,11 +108,15 @@ impl FungibleToken {
         }
         let mut account = self.get_account(&owner_id);
         let current_allowance = account.get_allowance(&escrow_account_id);
-        account.set_allowance(
-            &escrow_account_id,
-            current_allowance.saturating_sub(amount.0),
-        );
+        let allowance = current_allowance.saturating_sub(amount.0);
+        account.set_allowance(&escrow_account_id, allowance);
         self.set_account(&owner_id, &account);
+        Event::AllowanceChange {
+            owner_id,
+            escrow_account_id,
+            allowance: allowance.into(),
+        }
+        .emit();
         self.refund_storage(initial_storage);
     }
 
@@ -95,45 +131,33 @@ impl FungibleToken {
     ///   fixed storage price defined in the contract.
     #[payable]
     pub fn transfer_from(&mut self, owner_id: AccountId, new_owner_id: AccountId, amount: U128) {
+        self.assert_not_paused();
         let initial_storage = env::storage_usage();
         assert!(
             env::is_valid_account_id(new_owner_id.as_bytes()),
             "New owner's account ID is invalid"
         );
         let amount = amount.into();
-        if amount == 0 {
-            env::panic(b"Can't transfer 0 tokens");
-        }
-        assert_ne!(
-            owner_id, new_owner_id,
-            "The new owner should be different from the current owner"
-        );
-        // Retrieving the account from the state.
-        let mut account = self.get_account(&owner_id);
-
-        // Checking and updating unlocked balance
-        if account.balance < amount {
-            env::panic(b"Not enough balance");
-        }
-        account.balance -= amount;
 
         // If transferring by escrow, need to check and update allowance.
         let escrow_account_id = env::predecessor_account_id();
         if escrow_account_id != owner_id {
+            let mut account = self.get_account(&owner_id);
             let allowance = account.get_allowance(&escrow_account_id);
             if allowance < amount {
                 env::panic(b"Not enough allowance");
             }
             account.set_allowance(&escrow_account_id, allowance - amount);
+            self.set_account(&owner_id, &account);
+            Event::AllowanceChange {
+                owner_id: owner_id.clone(),
+                escrow_account_id,
+                allowance: (allowance - amount).into(),
+            }
+            .emit();
         }
 
-        // Saving the account back to the state.
-        self.set_account(&owner_id, &account);
-
-        // Deposit amount to the new owner and save the new account to the state.
-        let mut new_account = self.get_account(&new_owner_id);
-        new_account.balance += amount;
-        self.set_account(&new_owner_id, &new_account);
+        self.internal_transfer(&owner_id, &new_owner_id, amount, None);
         self.refund_storage(initial_storage);
     }
 
@@ -161,6 +185,12 @@ impl FungibleToken {
         self.get_account(&owner_id).balance.into()
     }
 
+    /// Returns the staked balance of the `owner_id` account, which isn't part of the balance
+    /// that can be transferred.
+    pub fn get_locked_balance(&self, owner_id: AccountId) -> U128 {
+        self.get_account(&owner_id).locked_balance.into()
+    }
+
     /// Returns current allowance of `escrow_account_id` for the account of `owner_id`.
     ///
     /// NOTE: Other contracts should not rely on this information, because by the moment a contract
@@ -178,6 +208,70 @@ impl FungibleToken {
 }
 
 impl FungibleToken {
+    /// Creates the state with empty collections, without writing to storage.
+    fn internal_new(
+        owner_id: AccountId,
+        total_supply: Balance,
+        metadata: FungibleTokenMetadata,
+        validation_config: ValidationConfig,
+    ) -> Self {
+        Self {
+            accounts: UnorderedMap::new(b"31ec9a4c-af6f-44dd-a488-bfdc45c493a7".to_vec()),
+            total_supply,
+            owner_id: owner_id.clone(),
+            paused: false,
+            paused_at: 0,
+            paused_duration: 0,
+            metadata,
+            account_storage: LookupMap::new(b"5d7f9b1c-3e5a-4c7e-9a1b-7d3f5b9c1e3a".to_vec()),
+            account_storage_usage: 0,
+            validation_config,
+            user_id: 0,
+            user_map: LookupMap::new(b"2a543bc7-a03f-427f-98c4-aa34012fa358".to_vec()),
+            user_account_map: LookupMap::new(b"e1f4c0a2-6b3d-4e5f-9a7c-8d2b0f1e3c4a".to_vec()),
+            voter_profile_map: LookupMap::new(b"a9d08e6d-fe16-441e-9330-81f45b8a68b3".to_vec()),
+            voter_profile_history: LookupMap::new(b"8c1e3a5b-7d9f-4b2e-a6c8-0e2a4c6e8b1d".to_vec()),
+            voter_if_staked: LookupMap::new(b"0e9cdb00-e90a-4aed-8541-1fb2ea6a1538".to_vec()),
+            voter_stakes: LookupMap::new(b"de89b05f-e35d-4237-bba9-64b2baac1ca8".to_vec()),
+            voter_timeline: LookupMap::new(b"f3a1c5e7-9b2d-4f6a-8c0e-2d4b6f8a1c3e".to_vec()),
+            juror_applied_for: LookupMap::new(b"6a2e4c8f-1b3d-4e5a-9c7f-0d2b4f6a8e1c".to_vec()),
+            juror_applications: LookupMap::new(b"96a7bcb7-5c9e-4af5-b33c-eb7a4c3a38a1".to_vec()),
+            juror_applicants: LookupMap::new(b"3b5d7f9a-2c4e-4a6b-8d1f-5e7a9c1b3d5f".to_vec()),
+            juror_stake_trees: LookupMap::new(b"aaabae72-8319-42f0-9e8b-19d5db7d176d".to_vec()),
+            juror_stake_unique_id: 0,
+            selected_juror: LookupMap::new(b"827a829e-e9b9-4e85-908b-49f8dab0e496".to_vec()),
+            voter_appeals: LookupMap::new(b"9e3c5a7b-2d4f-4a6c-8e1b-3f5d7b9a2c4e".to_vec()),
+            juror_vote_unique_id: 0,
+            voter_ruling: LookupMap::new(b"4b8e2d6f-1a3c-4f5e-b7d9-0c2e4a6b8d1f".to_vec()),
+            juror_reputation: LookupMap::new(b"c4e6a8b0-2d4f-4e6a-8c0e-4f6b8d0a2c4e".to_vec()),
+            draw_transcripts: LookupMap::new(b"e7b9d1f3-5a7c-4e9b-a1d3-6f8b0d2e4a6c".to_vec()),
+            jury_service: LookupMap::new(b"1f3b5d7a-9c2e-4b4d-8f6a-2c4e6a8d0b1f".to_vec()),
+            declared_relationships: LookupMap::new(
+                b"5a7c9e1b-3d5f-4a7b-9c2d-6e8a0c2f4b6d".to_vec(),
+            ),
+            voter_vouches: LookupMap::new(b"8d0f2b4e-6a8c-4d0e-b2f4-9a1c3e5b7d9f".to_vec()),
+            departments: UnorderedSet::new(b"2e4a6c8f-0b2d-4f6a-8c1e-3b5d7f9a1c3e".to_vec()),
+            department_experts: LookupMap::new(b"6c8e0a2d-4f6b-4a8c-9e1f-5d7b9c1e3a5f".to_vec()),
+            expertise_applications: LookupMap::new(
+                b"9f1b3d5a-7c9e-4b1d-a3f5-8e0c2a4d6b8f".to_vec(),
+            ),
+            expertise_claims: LookupMap::new(b"3c5e7a9b-1d3f-4c5e-b7a9-0f2d4b6e8a0c".to_vec()),
+            expertise_application_id: 0,
+            migration: None,
+            version_1_juror_stakes: LookupMap::new(
+                b"b2d4f6a8-0c2e-4b6d-8f1a-3c5e7b9d1f2a".to_vec(),
+            ),
+        }
+    }
+
+    /// Measures the storage of an account and registers the storage of the owner.
+    fn register_owner(&mut self) {
+        self.measure_account_storage_usage();
+        // The owner is registered without a deposit, the contract pays for its registration
+        self.account_storage
+            .insert(&self.owner_id, &AccountStorage::default());
+    }
+
     /// Helper method to get the account details for `owner_id`.
     fn get_account(&self, owner_id: &AccountId) -> Account {
         assert!(
@@ -193,13 +287,137 @@ impl FungibleToken {
     /// Helper method to set the account details for `owner_id` to the state.
     fn set_account(&mut self, owner_id: &AccountId, account: &Account) {
         let account_hash = env::sha256(owner_id.as_bytes());
-        if account.balance > 0 || !account.allowances.is_empty() {
+        if account.balance > 0 || account.locked_balance > 0 || !account.allowances.is_empty() {
             self.accounts.insert(&account_hash, &account);
         } else {
             self.accounts.remove(&account_hash);
         }
     }
 
+    /// Moves `amount` from the balance of `sender_id` to the balance of `receiver_id`,
+    /// shared by the NEP-21 and the NEP-141 transfers.
+    fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        if amount == 0 {
+            env::panic(b"Can't transfer 0 tokens");
+        }
+        assert_ne!(
+            sender_id, receiver_id,
+            "The new owner should be different from the current owner"
+        );
+        let mut sender = self.get_account(sender_id);
+        if sender.balance < amount {
+            env::panic(b"Not enough balance");
+        }
+        sender.balance -= amount;
+        self.set_account(sender_id, &sender);
+
+        let mut receiver = self.get_account(receiver_id);
+        receiver.balance += amount;
+        self.set_account(receiver_id, &receiver);
+        FtEvent::FtTransfer(vec![FtTransfer {
+            old_owner_id: sender_id.clone(),
+            new_owner_id: receiver_id.clone(),
+            amount: amount.into(),
+            memo,
+        }])
+        .emit();
+    }
+
+    /// Measures the bytes of storage used to register an account with the longest account ID
+    /// along with its token account.
+    fn measure_account_storage_usage(&mut self) {
+        let initial_storage = env::storage_usage();
+        let tmp_account_id = "a".repeat(64);
+        self.account_storage
+            .insert(&tmp_account_id, &AccountStorage::default());
+        let mut tmp_account = self.get_account(&tmp_account_id);
+        tmp_account.balance = 1;
+        self.set_account(&tmp_account_id, &tmp_account);
+        self.account_storage_usage = env::storage_usage() - initial_storage;
+        tmp_account.balance = 0;
+        self.set_account(&tmp_account_id, &tmp_account);
+        self.account_storage.remove(&tmp_account_id);
+    }
+
+    /// Bills the storage written since `initial_storage` to the storage balance of
+    /// `account_id`, and credits back the storage released.
+    fn charge_storage(&mut self, account_id: &AccountId, initial_storage: StorageUsage) {
+        let mut storage = self.assert_registered(account_id);
+        let current_storage = env::storage_usage();
+        if current_storage > initial_storage {
+            storage.used += current_storage - initial_storage;
+            let required_deposit = self.storage_cost(&storage);
+            assert!(
+                required_deposit <= storage.deposit,
+                "Not enough storage balance, the required storage balance is {}, but the storage balance is {}",
+                required_deposit,
+                storage.deposit,
+            );
+        } else {
+            storage.used = storage
+                .used
+                .saturating_sub(initial_storage - current_storage);
+        }
+        self.account_storage.insert(account_id, &storage);
+    }
+
+    /// Credits the storage released since `initial_storage` to the storage balance of
+    /// `account_id`, if the account is still registered.
+    fn credit_storage(&mut self, account_id: &AccountId, initial_storage: StorageUsage) {
+        if let Some(mut storage) = self.account_storage.get(account_id) {
+            let released = initial_storage.saturating_sub(env::storage_usage());
+            storage.used = storage.used.saturating_sub(released);
+            self.account_storage.insert(account_id, &storage);
+        }
+    }
+
+    fn assert_owner(&self) {
+        if env::predecessor_account_id() != self.owner_id {
+            panic!("Only the owner can call this method");
+        }
+    }
+
+    fn assert_not_paused(&self) {
+        if self.migration.is_some() {
+            panic!("The contract state is being migrated");
+        }
+        if self.paused {
+            panic!("The contract is paused");
+        }
+    }
+
+    /// Returns the storage of `account_id`, which has to be registered with `storage_deposit`.
+    fn assert_registered(&self, account_id: &AccountId) -> AccountStorage {
+        match self.account_storage.get(account_id) {
+            Some(storage) => storage,
+            None => panic!("The account {} is not registered", account_id),
+        }
+    }
+
+    fn assert_one_yocto(&self) {
+        assert_eq!(
+            env::attached_deposit(),
+            1,
+            "Requires attached deposit of exactly 1 yoctoNEAR"
+        );
+    }
+
+    /// Storage balance that isn't used by the registration and the storage of the account.
+    fn storage_available(&self, storage: &AccountStorage) -> Balance {
+        storage.deposit.saturating_sub(self.storage_cost(storage))
+    }
+
+    /// Cost of the registration and the storage used by the account.
+    fn storage_cost(&self, storage: &AccountStorage) -> Balance {
+        Balance::from(self.account_storage_usage + storage.used) * STORAGE_PRICE_PER_BYTE
+    }
+
     fn refund_storage(&self, initial_storage: StorageUsage) {
         let current_storage = env::storage_usage();
         let attached_deposit = env::attached_deposit();
@@ -225,12 +443,11 @@ impl FungibleToken {
 }
 
 
-/// Burn and mint
+/// Mint
 #[near_bindgen]
 impl FungibleToken {
     fn _mint(&mut self, owner_id: &AccountId, amount: u128) {
         if !owner_id.is_empty() {
-            let initial_storage = env::storage_usage();
             if amount == 0 {
                 env::panic(b"Can't transfer 0 tokens");
             }
@@ -242,26 +459,73 @@ impl FungibleToken {
             account.balance += amount;
             self.set_account(&owner_id, &account);
             self.total_supply = self.total_supply + amount;
-            self.refund_storage(initial_storage);
+            FtEvent::FtMint(vec![FtMint {
+                owner_id: owner_id.clone(),
+                amount: amount.into(),
+                memo: None,
+            }])
+            .emit();
         }
     }
+}
 
-    fn burn(&mut self, owner_id: &AccountId, amount: u128) {
-        if !owner_id.is_empty() {
-            let initial_storage = env::storage_usage();
-            if amount == 0 {
-                env::panic(b"Can't transfer 0 tokens");
-            }
-            assert!(
-                env::is_valid_account_id(owner_id.as_bytes()),
-                "Owner's account ID is invalid"
-            );
-            let mut account = self.get_account(&owner_id);
+/// Lock and unlock of staked balance, the total supply doesn't change while tokens are staked.
+/// `ft_balance_of` includes the locked balance, so only forfeits are logged as NEP-141 events
+#[near_bindgen]
+impl FungibleToken {
+    /// Moves `amount` from the balance to the locked balance of `owner_id`.
+    fn lock_balance(&mut self, owner_id: &AccountId, amount: u128) {
+        if amount == 0 {
+            env::panic(b"Can't stake 0 tokens");
+        }
+        let mut account = self.get_account(owner_id);
+        assert!(
+            account.balance >= amount,
+            "Not enough balance to stake {}, the balance is {}",
+            amount,
+            account.balance
+        );
+        account.balance -= amount;
+        account.locked_balance += amount;
+        self.set_account(owner_id, &account);
+    }
 
-            account.balance -= amount;
-            self.set_account(&owner_id, &account);
-            self.total_supply = self.total_supply - amount;
-            self.refund_storage(initial_storage);
+    /// Moves `amount` from the locked balance back to the balance of `owner_id`.
+    fn unlock_balance(&mut self, owner_id: &AccountId, amount: u128) {
+        let mut account = self.get_account(owner_id);
+        account.locked_balance -= amount;
+        account.balance += amount;
+        self.set_account(owner_id, &account);
+    }
+
+    /// Burns `amount` of the locked balance of `owner_id`. The caller can credit the
+    /// forfeited tokens to other accounts with `credit_forfeited`.
+    fn forfeit_locked_balance(&mut self, owner_id: &AccountId, amount: u128) {
+        let mut account = self.get_account(owner_id);
+        account.locked_balance -= amount;
+        self.set_account(owner_id, &account);
+        self.total_supply -= amount;
+        if amount > 0 {
+            FtEvent::FtBurn(vec![FtBurn {
+                owner_id: owner_id.clone(),
+                amount: amount.into(),
+                memo: Some("forfeited".to_string()),
+            }])
+            .emit();
         }
     }
+
+    /// Mints `amount` of forfeited tokens back to the balance of `owner_id`.
+    fn credit_forfeited(&mut self, owner_id: &AccountId, amount: u128) {
+        let mut account = self.get_account(owner_id);
+        account.balance += amount;
+        self.set_account(owner_id, &account);
+        self.total_supply += amount;
+        FtEvent::FtMint(vec![FtMint {
+            owner_id: owner_id.clone(),
+            amount: amount.into(),
+            memo: Some("forfeited".to_string()),
+        }])
+        .emit();
+    }
 }
@@ -0,0 +1,251 @@
+use super::super::{
+    ApplicationPhase, ExpertiseApplication, FungibleToken, EXPERTISE_APPLICATION_ID_START,
+};
+use crate::shivarthu::events::Event;
+use near_sdk::collections::{UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId};
+
+/// Expert departments. A validated voter claims expertise in a department with a stake,
+/// and the claim goes through the juror application, draw, commit, reveal, appeal and
+/// settlement phases of a voter application, with jurors from the experts of the department.
+#[near_bindgen]
+impl FungibleToken {
+    /// Owner adds a department, its first experts are added with `add_department_expert`.
+    pub fn add_department(&mut self, department: String) {
+        self.assert_owner();
+        if department.is_empty() {
+            panic!("Department name is empty");
+        }
+        if !self.departments.insert(&department) {
+            panic!("Department already exists");
+        }
+        Event::DepartmentAdd { department }.emit();
+    }
+
+    /// Owner adds an expert to the department, to seed the juror pool of a new department.
+    pub fn add_department_expert(&mut self, department: String, account_id: AccountId) {
+        self.assert_owner();
+        self.assert_department(&department);
+        let user_id = self.get_user_id(&account_id);
+        self.insert_department_expert(&department, user_id);
+    }
+
+    pub fn get_departments(&self) -> Vec<String> {
+        self.departments.to_vec()
+    }
+
+    pub fn is_department_expert(&self, department: String, user_id: u128) -> bool {
+        match self.department_experts.get(&department) {
+            Some(experts) => experts.contains(&user_id),
+            None => false,
+        }
+    }
+
+    /// Returns the user ids of the experts of the department, who are its juror pool.
+    pub fn get_department_experts(&self, department: String) -> Vec<u128> {
+        match self.department_experts.get(&department) {
+            Some(experts) => experts.to_vec(),
+            None => vec![],
+        }
+    }
+
+    /// Claims expertise in the department with the IPFS hash of the proof of expertise,
+    /// locking `stake` as the stake of the application. Returns the application id, which
+    /// identifies the application in the expertise methods below and in the voter
+    /// application views. A rejected claim, or a claim the jurors gave no ruling on, can be
+    /// made again.
+    pub fn claim_expertise(
+        &mut self,
+        department: String,
+        profile_hash: String,
+        stake: u128,
+    ) -> U128 {
+        self.assert_not_paused();
+        let account_id = env::signer_account_id();
+        let user_id = self.get_user_id(&account_id);
+        if !self.is_validated_voter(account_id.clone()) {
+            panic!("Only validated voters can claim expertise");
+        }
+        self.assert_department(&department);
+        if self.is_department_expert(department.clone(), user_id) {
+            panic!("You are already an expert of the department");
+        }
+        let mut claims = match self.expertise_claims.get(&user_id) {
+            Some(claims) => claims,
+            None => {
+                let claimsidstring = format!(
+                    "expertiseclaimsuserid{}uniqueid{}",
+                    user_id, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                UnorderedMap::new(claimsidstring.into_bytes())
+            }
+        };
+        let previous_application_id = claims.get(&department);
+        if let Some(previous_application_id) = previous_application_id {
+            match self.get_voter_application_phase(previous_application_id.into()) {
+                ApplicationPhase::NotStarted | ApplicationPhase::Settled => {}
+                _ => panic!("Expertise application is in progress"),
+            }
+            self.clear_voter_application(previous_application_id);
+        }
+        let initial_storage = env::storage_usage();
+        if let Some(previous_application_id) = previous_application_id {
+            self.expertise_applications.remove(&previous_application_id);
+        }
+        self.expertise_application_id += 1;
+        let application_id = EXPERTISE_APPLICATION_ID_START + self.expertise_application_id;
+        let expertise_application = ExpertiseApplication {
+            user_id,
+            department: department.clone(),
+            profile_hash: profile_hash.clone(),
+        };
+        self.expertise_applications
+            .insert(&application_id, &expertise_application);
+        claims.insert(&department, &application_id);
+        self.expertise_claims.insert(&user_id, &claims);
+        self.lock_balance(&account_id, stake);
+        self.voter_stakes.insert(&application_id, &stake);
+        Event::ExpertiseClaim {
+            application_id: application_id.into(),
+            user_id: user_id.into(),
+            department,
+            profile_hash,
+        }
+        .emit();
+        self.open_voter_application(application_id);
+        self.charge_storage(&account_id, initial_storage);
+        application_id.into()
+    }
+
+    pub fn get_expertise_application(&self, application_id: U128) -> Option<ExpertiseApplication> {
+        self.expertise_applications.get(&application_id.0)
+    }
+
+    /// Returns the id of the latest expertise application of the user in the department.
+    pub fn get_expertise_application_id(&self, user_id: u128, department: String) -> Option<U128> {
+        self.expertise_claims
+            .get(&user_id)
+            .and_then(|claims| claims.get(&department))
+            .map(U128)
+    }
+
+    /// Same as `apply_jurors` for the expertise application, only the experts of the
+    /// department can apply.
+    pub fn apply_expertise_jurors(&mut self, application_id: U128, stake: u128) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_apply_jurors(application_id, stake);
+    }
+
+    pub fn draw_expertise_jurors(&mut self, application_id: U128) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_draw_jurors(application_id);
+    }
+
+    pub fn withdraw_expertise_juror_stake(&mut self, application_id: U128) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_withdraw_juror_stake(application_id);
+    }
+
+    pub fn commit_expertise_juror_vote(&mut self, application_id: U128, vote_commit: String) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_commit_juror_vote(application_id, vote_commit);
+    }
+
+    pub fn reveal_expertise_juror_vote(&mut self, application_id: U128, vote: String) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_reveal_juror_vote(application_id, vote);
+    }
+
+    /// Same as `appeal_voter_application` for the expertise application. The application
+    /// is settled with `settle_voter_application`, which adds the claimant to the experts
+    /// of the department if the claim is accepted.
+    pub fn appeal_expertise_application(&mut self, application_id: U128) {
+        let application_id = application_id.0;
+        self.assert_expertise_application(application_id);
+        self.internal_appeal_voter_application(application_id);
+    }
+
+    /// Returns the user id of the claimant of an expertise application, or `voter_user_id`
+    /// itself for a voter application.
+    pub(crate) fn get_applicant_user_id(&self, voter_user_id: u128) -> u128 {
+        match self.expertise_applications.get(&voter_user_id) {
+            Some(expertise_application) => expertise_application.user_id,
+            None => voter_user_id,
+        }
+    }
+
+    pub(crate) fn insert_department_expert(&mut self, department: &str, user_id: u128) {
+        let department = department.to_string();
+        let mut experts = match self.department_experts.get(&department) {
+            Some(experts) => experts,
+            None => {
+                let expertsidstring = format!(
+                    "departmentexperts{}uniqueid{}",
+                    department, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                UnorderedSet::new(expertsidstring.into_bytes())
+            }
+        };
+        experts.insert(&user_id);
+        self.department_experts.insert(&department, &experts);
+        Event::DepartmentExpertAdd {
+            department,
+            user_id: user_id.into(),
+        }
+        .emit();
+    }
+
+    /// Removes the user from the experts of every department and clears its expertise
+    /// claims, when its voter profile is reset. Each department has to be validated again.
+    pub(crate) fn revoke_expertise(&mut self, user_id: u128) {
+        for department in self.departments.iter() {
+            if let Some(mut experts) = self.department_experts.get(&department) {
+                if experts.remove(&user_id) {
+                    self.department_experts.insert(&department, &experts);
+                    Event::DepartmentExpertRemove {
+                        department,
+                        user_id: user_id.into(),
+                    }
+                    .emit();
+                }
+            }
+        }
+        if let Some(mut claims) = self.expertise_claims.get(&user_id) {
+            let account_id = self.get_user_account_id(user_id);
+            for application_id in claims.values() {
+                match self.get_voter_application_phase(application_id.into()) {
+                    ApplicationPhase::NotStarted | ApplicationPhase::Settled => {}
+                    _ => panic!("Expertise application is in progress"),
+                }
+                self.clear_voter_application(application_id);
+                let initial_storage = env::storage_usage();
+                self.expertise_applications.remove(&application_id);
+                self.credit_storage(&account_id, initial_storage);
+            }
+            let initial_storage = env::storage_usage();
+            claims.clear();
+            self.expertise_claims.remove(&user_id);
+            self.credit_storage(&account_id, initial_storage);
+        }
+    }
+
+    fn assert_department(&self, department: &str) {
+        if !self.departments.contains(&department.to_string()) {
+            panic!("Department {} doesnot exist", department);
+        }
+    }
+
+    fn assert_expertise_application(&self, application_id: u128) {
+        if self.expertise_applications.get(&application_id).is_none() {
+            panic!("Expertise application doesnot exist");
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use super::super::FungibleToken;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, ext_contract, near_bindgen, serde_json, AccountId, Balance, Gas, Promise, PromiseResult,
+};
+
+/// Gas kept for the `ft_resolve_transfer` callback of `ft_transfer_call`.
+const GAS_FOR_RESOLVE_TRANSFER: Gas = 5_000_000_000_000;
+/// Gas kept by `ft_transfer_call`, the rest of the prepaid gas goes to `ft_on_transfer`.
+const GAS_FOR_FT_TRANSFER_CALL: Gas = 25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
+const NO_DEPOSIT: Balance = 0;
+
+/// Receiver of `ft_transfer_call`, returns the amount of the transferred tokens it didn't use.
+#[ext_contract(ext_fungible_token_receiver)]
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> near_sdk::PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+trait FungibleTokenResolver {
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128;
+}
+
+/// NEP-141 fungible token standard
+#[near_bindgen]
+impl FungibleToken {
+    /// Transfers `amount` of tokens from the caller of the contract (`predecessor_id`) to
+    /// `receiver_id`.
+    /// Requirements:
+    /// * Caller of the method has to attach exactly 1 yoctoNEAR.
+    /// * `receiver_id` has to be registered with `storage_deposit`.
+    #[payable]
+    pub fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_not_paused();
+        self.assert_one_yocto();
+        self.assert_registered(&receiver_id);
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+    }
+
+    /// Transfers `amount` of tokens to the contract `receiver_id` and calls `ft_on_transfer`
+    /// on it with `msg`. The tokens the receiver returns as unused are refunded to the
+    /// caller in `ft_resolve_transfer`, which returns the amount of tokens used.
+    /// Requirements:
+    /// * Same as `ft_transfer`.
+    /// * Caller of the method has to attach more than 30 TGas.
+    #[payable]
+    pub fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> Promise {
+        assert!(
+            env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL,
+            "More gas is required"
+        );
+        let sender_id = env::predecessor_account_id();
+        self.ft_transfer(receiver_id.clone(), amount, memo);
+        ext_fungible_token_receiver::ft_on_transfer(
+            sender_id.clone(),
+            amount,
+            msg,
+            &receiver_id,
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+        )
+        .then(ext_self::ft_resolve_transfer(
+            sender_id,
+            receiver_id,
+            amount,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+    }
+
+    /// Callback of `ft_transfer_call`, refunds the unused tokens to `sender_id` as long as
+    /// `receiver_id` still has them and returns the amount of tokens used.
+    /// If `ft_on_transfer` failed, all the tokens are unused. The callback isn't blocked
+    /// while the contract is paused, so the refund of a transfer made before the pause is kept.
+    pub fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            "Method ft_resolve_transfer is private"
+        );
+        let amount: Balance = amount.into();
+        let unused_amount = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(value) => match serde_json::from_slice::<U128>(&value) {
+                Ok(unused_amount) => std::cmp::min(amount, unused_amount.0),
+                Err(_) => amount,
+            },
+            PromiseResult::Failed => amount,
+        };
+        if unused_amount > 0 {
+            let receiver_balance = self.get_account(&receiver_id).balance;
+            let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+            if refund_amount > 0 {
+                self.internal_transfer(
+                    &receiver_id,
+                    &sender_id,
+                    refund_amount,
+                    Some("refund".to_string()),
+                );
+                return (amount - refund_amount).into();
+            }
+        }
+        amount.into()
+    }
+
+    /// Returns total supply of tokens.
+    pub fn ft_total_supply(&self) -> U128 {
+        self.total_supply.into()
+    }
+
+    /// Returns balance of the `account_id` account, including its locked balance.
+    pub fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        let account = self.get_account(&account_id);
+        (account.balance + account.locked_balance).into()
+    }
+}
@@ -0,0 +1,34 @@
+use super::super::{FungibleToken, FungibleTokenMetadata};
+use crate::shivarthu::events::Event;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::near_bindgen;
+
+/// NEP-148 fungible token metadata
+#[near_bindgen]
+impl FungibleToken {
+    pub fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.clone()
+    }
+
+    /// Owner sets the data URL of the icon, or removes it.
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.assert_owner();
+        self.metadata.icon = icon;
+        Event::FtMetadataUpdate.emit();
+    }
+
+    /// Owner sets the reference JSON file and its sha256 hash, or removes them.
+    pub fn set_reference(
+        &mut self,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    ) {
+        self.assert_owner();
+        let mut metadata = self.metadata.clone();
+        metadata.reference = reference;
+        metadata.reference_hash = reference_hash;
+        metadata.assert_valid();
+        self.metadata = metadata;
+        Event::FtMetadataUpdate.emit();
+    }
+}
@@ -0,0 +1,212 @@
+use super::super::{
+    AccountV1, FungibleToken, FungibleTokenMetadata, FungibleTokenV1, JurorStakesV1, MigrationV1,
+    ValidationConfig, VersionedFungibleToken, Voter, STATE_VERSION,
+};
+use crate::shivarthu::account::Account;
+use crate::shivarthu::events::Event;
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{UnorderedMap, Vector};
+use near_sdk::{env, near_bindgen, AccountId};
+
+/// Contract state upgrades
+#[near_bindgen]
+impl FungibleToken {
+    /// Converts the state written by an earlier version of the contract to the current
+    /// layout. The owner calls it after deploying the new code, before any other method,
+    /// which can't read the state till it is migrated.
+    ///
+    /// The version 1 state has no owner, so it is migrated by the contract account, which
+    /// becomes the owner. Its collections are converted afterwards in batches: the owner
+    /// adds the accounts of the users with `add_migrated_accounts` and calls
+    /// `migrate_step` till it returns true. The methods other than the views and the owner
+    /// methods are blocked till then.
+    #[init]
+    pub fn migrate() -> Self {
+        let state = VersionedFungibleToken::read();
+        if env::predecessor_account_id() != state.owner_id() {
+            panic!("Only the owner can call this method");
+        }
+        let state = match state {
+            VersionedFungibleToken::V1(state) => *state,
+            VersionedFungibleToken::V2(_) => {
+                panic!("The contract state is already at version {}", STATE_VERSION)
+            }
+        };
+        let mut ft = Self::internal_new(
+            env::current_account_id(),
+            state.total_supply,
+            FungibleTokenMetadata::default(),
+            ValidationConfig::default(),
+        );
+        ft.user_id = state.user_id;
+        ft.juror_stake_unique_id = state.juror_stake_unique_id;
+        // Both layouts of the accounts map are the same collection, only its values are
+        // re-encoded by `migrate_step`
+        ft.accounts = UnorderedMap::try_from_slice(&state.accounts.try_to_vec().unwrap()).unwrap();
+        ft.register_owner();
+        ft.migration = Some(MigrationV1 {
+            state,
+            next_account_index: 0,
+            added_accounts: 0,
+            next_user_id: 1,
+        });
+        VersionedFungibleToken::write_current_version();
+        ft
+    }
+
+    /// Owner adds the accounts of the users of the version 1 state, which only maps the
+    /// accounts to their user ids. They can be added in any order and in several calls.
+    pub fn add_migrated_accounts(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_owner();
+        let mut migration = self.take_migration();
+        for account_id in account_ids.iter() {
+            let user_id = match self.user_map.get(account_id) {
+                Some(user_id) => user_id,
+                None => panic!("User id doesnot exist for {}", account_id),
+            };
+            if self.user_account_map.insert(&user_id, account_id).is_some() {
+                panic!("Account of user id {} is given twice", user_id);
+            }
+            migration.added_accounts += 1;
+        }
+        self.migration = Some(migration);
+    }
+
+    /// Owner migrates up to `limit` accounts, then up to `limit` users of the version 1
+    /// state. The voter profiles are re-encoded and the applications in progress are
+    /// cancelled, as they have no timeline to continue with: the burned stakes of the voter
+    /// and of the applicants who weren't drawn are minted back, and the drawn jurors claim
+    /// theirs with `claim_version_1_juror_stake`. Returns true once the migration is
+    /// complete.
+    pub fn migrate_step(&mut self, limit: u64) -> bool {
+        self.assert_owner();
+        let mut migration = self.take_migration();
+        let mut steps = 0;
+        let accounts_len = migration.state.accounts.len();
+        if migration.next_account_index < accounts_len {
+            let mut values: Vector<AccountV1> = Vector::try_from_slice(
+                &migration
+                    .state
+                    .accounts
+                    .values_as_vector()
+                    .try_to_vec()
+                    .unwrap(),
+            )
+            .unwrap();
+            while migration.next_account_index < accounts_len && steps < limit {
+                let index = migration.next_account_index;
+                let account = values.get(index).unwrap();
+                let account = Account {
+                    balance: account.balance,
+                    locked_balance: 0,
+                    allowances: account.allowances,
+                };
+                values.replace_raw(index, &account.try_to_vec().unwrap());
+                migration.next_account_index += 1;
+                steps += 1;
+            }
+        }
+        if steps < limit && migration.next_user_id <= migration.state.user_id {
+            if migration.added_accounts != migration.state.user_id {
+                panic!(
+                    "Accounts of all the {} users are needed",
+                    migration.state.user_id
+                );
+            }
+            while migration.next_user_id <= migration.state.user_id && steps < limit {
+                let user_id = migration.next_user_id;
+                self.migrate_v1_user(&mut migration.state, user_id);
+                migration.next_user_id += 1;
+                steps += 1;
+            }
+        }
+        let complete = migration.next_account_index == accounts_len
+            && migration.next_user_id > migration.state.user_id;
+        if complete {
+            Event::StateMigrate {
+                from_version: 1,
+                to_version: STATE_VERSION,
+            }
+            .emit();
+        } else {
+            self.migration = Some(migration);
+        }
+        complete
+    }
+
+    pub fn is_migrating(&self) -> bool {
+        self.migration.is_some()
+    }
+
+    /// Juror drawn for a version 1 application gets back the stake it burned to apply.
+    pub fn claim_version_1_juror_stake(&mut self, voter_user_id: u128) {
+        self.assert_not_paused();
+        let account_id = env::signer_account_id();
+        let juror_user_id = self.get_user_id(&account_id);
+        let mut juror_stakes = match self.version_1_juror_stakes.get(&voter_user_id) {
+            Some(juror_stakes) => juror_stakes,
+            None => panic!("No version 1 juror stakes for the voter"),
+        };
+        let stake = match juror_stakes.stakes.remove(&juror_user_id) {
+            Some(stake) => stake,
+            None => panic!("No version 1 juror stake to claim"),
+        };
+        juror_stakes.drawn_jurors.remove(&juror_user_id);
+        self.refund_burned_stake(juror_user_id, stake);
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        VersionedFungibleToken::stored_version()
+    }
+}
+
+impl FungibleToken {
+    fn take_migration(&mut self) -> MigrationV1 {
+        match self.migration.take() {
+            Some(migration) => migration,
+            None => panic!("The contract state is not being migrated"),
+        }
+    }
+
+    /// Re-encodes the voter profile of the user and cancels its version 1 application.
+    fn migrate_v1_user(&mut self, state: &mut FungibleTokenV1, user_id: u128) {
+        if let Some(voter) = state.voter_profile_map.remove(&user_id) {
+            let voter = Voter {
+                profile_hash: voter.profile_hash,
+                kyc_done: voter.kyc_done,
+                kyc_rejected: false,
+            };
+            self.voter_profile_map.insert(&user_id, &voter);
+        }
+        state.voter_if_staked.remove(&user_id);
+        if let Some(stake) = state.voter_stakes.remove(&user_id) {
+            self.refund_burned_stake(user_id, stake);
+        }
+        let mut stakes = match state.user_juror_stakes.remove(&user_id) {
+            Some(stakes) => stakes,
+            None => return,
+        };
+        // The applicants who weren't drawn are still in the clone of the stakes
+        if let Some(mut stakes_clone) = state.user_juror_stakes_clone.remove(&user_id) {
+            for (juror_user_id, stake) in stakes_clone.to_vec() {
+                stakes.remove(&juror_user_id);
+                self.refund_burned_stake(juror_user_id, stake);
+            }
+            stakes_clone.clear();
+        }
+        if let Some(drawn_jurors) = state.selected_juror.remove(&user_id) {
+            let juror_stakes = JurorStakesV1 {
+                stakes,
+                drawn_jurors,
+            };
+            self.version_1_juror_stakes.insert(&user_id, &juror_stakes);
+        }
+    }
+
+    fn refund_burned_stake(&mut self, user_id: u128, stake: u128) {
+        if stake > 0 {
+            let account_id = self.get_user_account_id(user_id);
+            self._mint(&account_id, stake);
+        }
+    }
+}
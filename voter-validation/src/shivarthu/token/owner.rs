@@ -0,0 +1,54 @@
+use super::super::FungibleToken;
+use crate::shivarthu::events::Event;
+use near_sdk::{env, near_bindgen, AccountId};
+
+/// Owner of the contract and the emergency pause
+#[near_bindgen]
+impl FungibleToken {
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    /// Owner hands over the owner methods to `new_owner_id`.
+    pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+        assert!(
+            env::is_valid_account_id(new_owner_id.as_bytes()),
+            "New owner's account ID is invalid"
+        );
+        let old_owner_id = std::mem::replace(&mut self.owner_id, new_owner_id.clone());
+        Event::OwnershipTransfer {
+            old_owner_id,
+            new_owner_id,
+        }
+        .emit();
+    }
+
+    /// Owner stops transfers, storage management and voter validation, e.g. when a bug
+    /// is found. Views, the owner methods and the callbacks of transfers made before the
+    /// pause still work. The phase deadlines of the applications are extended by the
+    /// length of the pause.
+    pub fn pause(&mut self) {
+        self.assert_owner();
+        if self.paused {
+            panic!("The contract is already paused");
+        }
+        self.paused = true;
+        self.paused_at = env::block_timestamp();
+        Event::ContractPause { paused: true }.emit();
+    }
+
+    pub fn unpause(&mut self) {
+        self.assert_owner();
+        if !self.paused {
+            panic!("The contract is not paused");
+        }
+        self.paused = false;
+        self.paused_duration += env::block_timestamp() - self.paused_at;
+        Event::ContractPause { paused: false }.emit();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
@@ -0,0 +1,153 @@
+use super::super::{
+    AccountStorage, FungibleToken, StorageBalance, StorageBalanceBounds, STORAGE_PRICE_PER_BYTE,
+};
+use crate::shivarthu::events::{FtBurn, FtEvent};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Balance, Promise};
+
+/// NEP-145 storage management
+#[near_bindgen]
+impl FungibleToken {
+    /// Registers `account_id`, or the caller if it's not given, with the attached deposit as
+    /// its storage balance, or adds the deposit to the storage balance of a registered account.
+    /// With `registration_only`, only the minimum storage balance is kept and the rest of the
+    /// deposit is refunded, all of it if the account is already registered.
+    #[payable]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        self.assert_not_paused();
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        assert!(
+            env::is_valid_account_id(account_id.as_bytes()),
+            "Account ID is invalid"
+        );
+        let registration_only = registration_only.unwrap_or(false);
+        match self.account_storage.get(&account_id) {
+            Some(mut storage) => {
+                if registration_only {
+                    env::log(b"The account is already registered, refunding the deposit");
+                    if amount > 0 {
+                        Promise::new(env::predecessor_account_id()).transfer(amount);
+                    }
+                } else {
+                    storage.deposit += amount;
+                    self.account_storage.insert(&account_id, &storage);
+                }
+            }
+            None => {
+                let min_balance = self.storage_balance_bounds().min.0;
+                if amount < min_balance {
+                    panic!(
+                        "The attached deposit is less than the minimum storage balance {}",
+                        min_balance
+                    );
+                }
+                let deposit = if registration_only {
+                    let refund = amount - min_balance;
+                    if refund > 0 {
+                        Promise::new(env::predecessor_account_id()).transfer(refund);
+                    }
+                    min_balance
+                } else {
+                    amount
+                };
+                let storage = AccountStorage { deposit, used: 0 };
+                self.account_storage.insert(&account_id, &storage);
+            }
+        }
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    /// Withdraws `amount` of the available storage balance of the caller, all of it if
+    /// `amount` is not given.
+    /// Requirements:
+    /// * Caller of the method has to attach exactly 1 yoctoNEAR.
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.assert_not_paused();
+        self.assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut storage = self.assert_registered(&account_id);
+        let available = self.storage_available(&storage);
+        let amount = amount.map(|amount| amount.0).unwrap_or(available);
+        if amount > available {
+            panic!(
+                "The amount is greater than the available storage balance {}",
+                available
+            );
+        }
+        storage.deposit -= amount;
+        self.account_storage.insert(&account_id, &storage);
+        if amount > 0 {
+            Promise::new(account_id.clone()).transfer(amount + 1);
+        }
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    /// Unregisters the caller and refunds its storage deposit. Returns false if the caller
+    /// isn't registered. An account with a token balance can only be unregistered with
+    /// `force`, which burns the balance. An account with staked tokens or with voter
+    /// validation records can't be unregistered.
+    /// Requirements:
+    /// * Caller of the method has to attach exactly 1 yoctoNEAR.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        self.assert_not_paused();
+        self.assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let storage = match self.account_storage.get(&account_id) {
+            Some(storage) => storage,
+            None => {
+                env::log(format!("The account {} is not registered", account_id).as_bytes());
+                return false;
+            }
+        };
+        let mut account = self.get_account(&account_id);
+        if account.locked_balance > 0 {
+            panic!("Can't unregister the account with a locked balance");
+        }
+        if storage.used > 0 {
+            panic!("Can't unregister the account with voter validation records");
+        }
+        if account.balance > 0 {
+            if !force.unwrap_or(false) {
+                panic!("Can't unregister the account with the positive balance without force");
+            }
+            let balance = account.balance;
+            account.balance = 0;
+            self.set_account(&account_id, &account);
+            self.total_supply -= balance;
+            FtEvent::FtBurn(vec![FtBurn {
+                owner_id: account_id.clone(),
+                amount: balance.into(),
+                memo: None,
+            }])
+            .emit();
+        }
+        self.account_storage.remove(&account_id);
+        Promise::new(account_id).transfer(storage.deposit + 1);
+        true
+    }
+
+    /// The minimum storage balance is the cost of registering an account.
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: (Balance::from(self.account_storage_usage) * STORAGE_PRICE_PER_BYTE).into(),
+            max: None,
+        }
+    }
+
+    /// Returns the storage balance of `account_id`, None if it isn't registered.
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.account_storage
+            .get(&account_id)
+            .map(|storage| StorageBalance {
+                total: storage.deposit.into(),
+                available: self.storage_available(&storage).into(),
+            })
+    }
+}
@@ -1,11 +1,21 @@
-use super::super::{FungibleToken, Voter};
-use near_sdk::collections::{LookupMap, LookupSet, TreeMap};
-use near_sdk::{env, near_bindgen, AccountId};
-use rand::distributions::WeightedIndex;
+use super::super::{
+    Appeal, ApplicationPhase, DrawTranscript, FungibleToken, JurorApplication,
+    JurorApplicationStatus, JurorApplicationView, JurorDraw, JurorReputation, ProfileHashRecord,
+    ValidationConfig, Voter, VoterApplicationTimeline, VoterRuling, Vouch, APPEAL_PERIOD,
+    COMMIT_PERIOD, DRAW_PERIOD, JUROR_APPLICATION_PERIOD, JUROR_INCENTIVE, JUROR_STAKE_TREE_K,
+    REVEAL_PERIOD,
+};
+use crate::shivarthu::events::Event;
+use near_sdk::collections::{LookupMap, UnorderedSet, Vector};
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, StorageUsage};
 use rand::prelude::*;
-use rand::{rngs::StdRng, SeedableRng};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha3::{Digest, Keccak256};
+use sortition_sum_tree::SortitionSumTree;
 
-pub fn get_rng(seed_vec: Vec<u8>) -> StdRng {
+pub fn get_rng(seed_vec: Vec<u8>) -> ChaCha20Rng {
     let mut seed = [0u8; 32];
     let mut counter = 0;
     for v in seed_vec.iter() {
@@ -13,10 +23,11 @@ pub fn get_rng(seed_vec: Vec<u8>) -> StdRng {
         counter += 1;
     }
 
-    let rng: StdRng = SeedableRng::from_seed(seed);
+    let rng: ChaCha20Rng = SeedableRng::from_seed(seed);
     rng
 }
-/// Voter Validation impl
+/// Voter Validation impl, the state written by each call is billed to the storage balance
+/// of the signer.
 #[near_bindgen]
 impl FungibleToken {
     pub fn get_user_id(&self, account_id: &AccountId) -> u128 {
@@ -29,36 +40,220 @@ impl FungibleToken {
         }
     }
 
+    pub fn get_user_account_id(&self, user_id: u128) -> AccountId {
+        let account_id_option = self.user_account_map.get(&user_id);
+        match account_id_option {
+            Some(account_id) => account_id,
+            None => {
+                panic!("AccountId doesnot exist for user id");
+            }
+        }
+    }
+
     pub fn get_voter_details(&self, user_id: u128) -> Voter {
         let voter_profile_option = self.voter_profile_map.get(&user_id);
         let voter = voter_profile_option.unwrap();
         voter
     }
 
-    pub fn get_voter_stake(&self, user_id: u128) -> u128 {
+    /// Returns true if the jurors have accepted the voter profile of `account_id`.
+    pub fn is_validated_voter(&self, account_id: AccountId) -> bool {
+        match self.user_map.get(&account_id) {
+            Some(user_id) => match self.voter_profile_map.get(&user_id) {
+                Some(voter) => voter.kyc_done,
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    pub fn get_voter_stake(&self, user_id: U128) -> u128 {
+        let user_id = user_id.0;
         let voter_stake_option = self.voter_stakes.get(&user_id);
         let voter_stake = voter_stake_option.unwrap();
         voter_stake
     }
 
     pub fn create_voter_profile(&mut self, profile_hash: String) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
         let account_id = env::signer_account_id();
         let account_id_exists_option = self.user_map.get(&account_id);
         let u = Voter {
             profile_hash,
             kyc_done: false,
+            kyc_rejected: false,
         };
         match account_id_exists_option {
             Some(_user_id) => panic!("Voter profile already exists"),
             None => {
                 self.user_id += 1;
                 self.user_map.insert(&account_id, &self.user_id);
+                self.user_account_map.insert(&self.user_id, &account_id);
                 self.voter_profile_map.insert(&self.user_id, &u);
+                self.push_voter_profile_history(self.user_id, u.profile_hash.clone());
+                self.charge_storage(&account_id, initial_storage);
+                Event::VoterProfileCreate {
+                    user_id: self.user_id.into(),
+                    account_id,
+                    profile_hash: u.profile_hash,
+                }
+                .emit();
+            }
+        }
+    }
+
+    /// Updates the IPFS hash of the voter profile, previous hashes are kept in the history.
+    /// If the profile was already validated or rejected, `kyc_done` is reset, the voter
+    /// application is cleared and the expertise of the voter is revoked, so the voter can
+    /// stake again for a fresh validation round.
+    pub fn update_voter_profile(&mut self, profile_hash: String) {
+        self.assert_not_paused();
+        let account_id = env::signer_account_id();
+        let user_id = match self.user_map.get(&account_id) {
+            Some(user_id) => user_id,
+            None => panic!("User id doesnot exist"),
+        };
+        match self.get_voter_application_phase(user_id.into()) {
+            ApplicationPhase::NotStarted | ApplicationPhase::Settled => {}
+            _ => panic!("Voter application is in progress"),
+        }
+        let mut voter = self.get_voter_details(user_id);
+        if voter.kyc_done || voter.kyc_rejected {
+            voter.kyc_done = false;
+            voter.kyc_rejected = false;
+            self.clear_voter_application(user_id);
+            self.revoke_expertise(user_id);
+        }
+        let initial_storage = env::storage_usage();
+        voter.profile_hash = profile_hash.clone();
+        self.voter_profile_map.insert(&user_id, &voter);
+        self.push_voter_profile_history(user_id, profile_hash.clone());
+        self.charge_storage(&account_id, initial_storage);
+        Event::VoterProfileUpdate {
+            user_id: user_id.into(),
+            account_id,
+            profile_hash,
+        }
+        .emit();
+    }
+
+    fn push_voter_profile_history(&mut self, user_id: u128, profile_hash: String) {
+        let mut history = match self.voter_profile_history.get(&user_id) {
+            Some(history) => history,
+            None => {
+                let historyidstring = format!("profilehistoryuserid{}", user_id);
+                Vector::new(historyidstring.into_bytes())
+            }
+        };
+        let record = ProfileHashRecord {
+            profile_hash,
+            timestamp: env::block_timestamp(),
+        };
+        history.push(&record);
+        self.voter_profile_history.insert(&user_id, &history);
+    }
+
+    /// Removes the stakes, jurors, votes and ruling of the settled voter application, the
+    /// nested collections before the maps holding them. Each account is credited the storage
+    /// it paid for, so callers measure their own storage after the application is cleared.
+    pub(crate) fn clear_voter_application(&mut self, user_id: u128) {
+        if let Some(mut juror_applicants) = self.juror_applicants.get(&user_id) {
+            let mut juror_applications = self.juror_applications.get(&user_id).unwrap();
+            // Each applicant added about one node to the stake tree, so its storage is shared
+            let initial_storage = env::storage_usage();
+            if let Some(juror_stake_tree) = self.juror_stake_trees.remove(&user_id) {
+                juror_stake_tree.clear();
+            }
+            let tree_storage = initial_storage - env::storage_usage();
+            let applicants = juror_applicants.len();
+            let mut initial_storage = env::storage_usage();
+            // The applicants are removed from the last one, the first one also paid for the
+            // collections of the application
+            while let Some(juror_user_id) = juror_applicants.pop() {
+                let juror_application = juror_applications.remove(&juror_user_id).unwrap();
+                let juror_account_id = self.get_user_account_id(juror_user_id);
+                // The stakes of the applicants who were never drawn are still locked
+                if juror_application.status == JurorApplicationStatus::Applied {
+                    self.unlock_balance(&juror_account_id, juror_application.stake.0);
+                }
+                if let Some(mut applied_for) = self.juror_applied_for.get(&juror_user_id) {
+                    applied_for.remove(&user_id);
+                    if applied_for.is_empty() {
+                        self.juror_applied_for.remove(&juror_user_id);
+                    } else {
+                        self.juror_applied_for.insert(&juror_user_id, &applied_for);
+                    }
+                }
+                let mut freed_tree_storage = tree_storage / applicants;
+                if juror_applicants.is_empty() {
+                    self.juror_applicants.remove(&user_id);
+                    self.juror_applications.remove(&user_id);
+                    freed_tree_storage += tree_storage % applicants;
+                }
+                self.credit_storage(&juror_account_id, initial_storage + freed_tree_storage);
+                initial_storage = env::storage_usage();
+            }
+        }
+        if let Some(mut jury_rounds) = self.selected_juror.get(&user_id) {
+            let mut transcripts = self.draw_transcripts.get(&user_id).unwrap();
+            let mut initial_storage = env::storage_usage();
+            while let Some(mut jury_set) = jury_rounds.pop() {
+                jury_set.clear();
+                let transcript = transcripts.pop().unwrap();
+                if jury_rounds.is_empty() {
+                    self.selected_juror.remove(&user_id);
+                    self.draw_transcripts.remove(&user_id);
+                }
+                self.credit_storage(&transcript.drawn_by, initial_storage);
+                initial_storage = env::storage_usage();
             }
         }
+        if let Some(mut appeals) = self.voter_appeals.get(&user_id) {
+            let mut initial_storage = env::storage_usage();
+            while let Some(appeal) = appeals.pop() {
+                if appeals.is_empty() {
+                    self.voter_appeals.remove(&user_id);
+                }
+                let appellant_account_id = self.get_user_account_id(appeal.appellant);
+                self.credit_storage(&appellant_account_id, initial_storage);
+                initial_storage = env::storage_usage();
+            }
+        }
+        if let Some(mut vouches) = self.voter_vouches.get(&user_id) {
+            let mut initial_storage = env::storage_usage();
+            while let Some(vouch) = vouches.pop() {
+                if vouches.is_empty() {
+                    self.voter_vouches.remove(&user_id);
+                }
+                let voucher_account_id = self.get_user_account_id(vouch.voucher);
+                self.credit_storage(&voucher_account_id, initial_storage);
+                initial_storage = env::storage_usage();
+            }
+        }
+        let initial_storage = env::storage_usage();
+        if let Some(voter_ruling) = self.voter_ruling.remove(&user_id) {
+            self.credit_storage(&voter_ruling.settled_by, initial_storage);
+        }
+        let initial_storage = env::storage_usage();
+        self.voter_if_staked.remove(&user_id);
+        self.voter_stakes.remove(&user_id);
+        self.voter_timeline.remove(&user_id);
+        let voter_account_id = self.get_user_account_id(self.get_applicant_user_id(user_id));
+        self.credit_storage(&voter_account_id, initial_storage);
+    }
+
+    /// Returns the IPFS hashes of the voter profile from the oldest to the current one.
+    pub fn get_voter_profile_history(&self, user_id: u128) -> Vec<ProfileHashRecord> {
+        match self.voter_profile_history.get(&user_id) {
+            Some(history) => history.to_vec(),
+            None => vec![],
+        }
     }
 
     pub fn create_voter_stake(&mut self, stake: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
         let account_id = env::signer_account_id();
         let account_id_exists_option = self.user_map.get(&account_id);
         match account_id_exists_option {
@@ -68,17 +263,17 @@ impl FungibleToken {
                 match if_staked_bool_option {
                     Some(if_staked_bool) => {
                         if !if_staked_bool {
-                            self.burn(&account_id, stake);
+                            self.lock_balance(&account_id, stake);
                             self.voter_if_staked.insert(&user_id, &true);
                             self.voter_stakes.insert(&user_id, &stake);
-                            println!("I am in voter_if_staked false ");
+                            self.open_voter_application(user_id);
                         }
                     }
                     None => {
-                        self.burn(&account_id, stake);
+                        self.lock_balance(&account_id, stake);
                         self.voter_if_staked.insert(&user_id, &true);
                         self.voter_stakes.insert(&user_id, &stake);
-                        println!("I am in voter_if_staked None");
+                        self.open_voter_application(user_id);
                     }
                 }
             }
@@ -86,154 +281,556 @@ impl FungibleToken {
                 panic!("User id doesnot exist");
             }
         }
+        self.charge_storage(&account_id, initial_storage);
     }
 
-    /// Apply Jurors with stake
+    pub(crate) fn open_voter_application(&mut self, user_id: u128) {
+        let now = self.unpaused_timestamp();
+        let juror_application_end = now + JUROR_APPLICATION_PERIOD;
+        let draw_end = juror_application_end + DRAW_PERIOD;
+        let commit_end = draw_end + COMMIT_PERIOD;
+        let reveal_end = commit_end + REVEAL_PERIOD;
+        let timeline = VoterApplicationTimeline {
+            juror_application_end,
+            draw_end,
+            commit_end,
+            reveal_end,
+            appeal_end: reveal_end + APPEAL_PERIOD,
+        };
+        self.voter_timeline.insert(&user_id, &timeline);
+        Event::VoterStake {
+            voter_user_id: user_id.into(),
+            stake: self.get_voter_stake(user_id.into()).into(),
+            juror_application_end: juror_application_end + self.paused_offset(),
+        }
+        .emit();
+    }
+
+    /// Returns the current phase of the voter application.
+    pub fn get_voter_application_phase(&self, voter_user_id: U128) -> ApplicationPhase {
+        let voter_user_id = voter_user_id.0;
+        if self.voter_ruling.get(&voter_user_id).is_some() {
+            return ApplicationPhase::Settled;
+        }
+        match self.voter_timeline.get(&voter_user_id) {
+            Some(timeline) => {
+                let now = self.unpaused_timestamp();
+                if now < timeline.juror_application_end {
+                    ApplicationPhase::JurorApplication
+                } else if now < timeline.draw_end {
+                    ApplicationPhase::Draw
+                } else if now < timeline.commit_end {
+                    ApplicationPhase::Commit
+                } else if now < timeline.reveal_end {
+                    ApplicationPhase::Reveal
+                } else if now < timeline.appeal_end {
+                    ApplicationPhase::Appeal
+                } else {
+                    ApplicationPhase::Settlement
+                }
+            }
+            None => ApplicationPhase::NotStarted,
+        }
+    }
+
+    /// Returns the deadlines of the voter application in block time. A later pause of the
+    /// contract moves them by the length of the pause.
+    pub fn get_voter_application_timeline(&self, voter_user_id: U128) -> VoterApplicationTimeline {
+        let voter_user_id = voter_user_id.0;
+        let timeline = self.get_unpaused_timeline(voter_user_id);
+        let offset = self.paused_offset();
+        VoterApplicationTimeline {
+            juror_application_end: timeline.juror_application_end + offset,
+            draw_end: timeline.draw_end + offset,
+            commit_end: timeline.commit_end + offset,
+            reveal_end: timeline.reveal_end + offset,
+            appeal_end: timeline.appeal_end + offset,
+        }
+    }
+
+    /// Timeline as stored, in the time returned by `unpaused_timestamp`.
+    fn get_unpaused_timeline(&self, voter_user_id: u128) -> VoterApplicationTimeline {
+        let timeline_option = self.voter_timeline.get(&voter_user_id);
+        match timeline_option {
+            Some(timeline) => timeline,
+            None => panic!("Voter application doesnot exist"),
+        }
+    }
+
+    /// Block timestamp without the time the contract was paused, it stands still during a
+    /// pause. The phase deadlines are stored in this time, so that a pause extends them.
+    fn unpaused_timestamp(&self) -> u64 {
+        let now = if self.paused {
+            self.paused_at
+        } else {
+            env::block_timestamp()
+        };
+        now - self.paused_duration
+    }
 
+    /// Difference between the block time and the unpaused time.
+    fn paused_offset(&self) -> u64 {
+        env::block_timestamp() - self.unpaused_timestamp()
+    }
+
+    fn assert_voter_application_phase(&self, voter_user_id: u128, phase: ApplicationPhase) {
+        let current_phase = self.get_voter_application_phase(voter_user_id.into());
+        if current_phase != phase {
+            panic!(
+                "Voter application is in {:?} phase, not in {:?} phase",
+                current_phase, phase
+            );
+        }
+    }
+
+    /// Apply Jurors with stake
     pub fn apply_jurors(&mut self, voter_username: AccountId, stake: u128) {
+        let voter_user_id = self.get_user_id(&voter_username);
+        self.internal_apply_jurors(voter_user_id, stake);
+    }
+
+    pub(crate) fn internal_apply_jurors(&mut self, voter_user_id: u128, stake: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
         let account_id = env::signer_account_id();
         let singer_juror_user = self.get_user_id(&account_id);
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::JurorApplication);
+        if let Some(conflict) = self.conflict_of_interest(voter_user_id, singer_juror_user) {
+            panic!("{}", conflict);
+        }
+        // Only the experts of the department judge an expertise application
+        if let Some(expertise_application) = self.expertise_applications.get(&voter_user_id) {
+            let department = expertise_application.department;
+            if !self.is_department_expert(department.clone(), singer_juror_user) {
+                panic!(
+                    "Only the experts of the {} department can be jurors for the application",
+                    department
+                );
+            }
+        }
+        if self
+            .get_juror_application(voter_user_id.into(), singer_juror_user)
+            .is_some()
+        {
+            panic!("You have already staked");
+        }
+        self.lock_balance(&account_id, stake);
+        let juror_application = JurorApplication {
+            stake: stake.into(),
+            status: JurorApplicationStatus::Applied,
+            round: None,
+            vote_commit: None,
+            vote: None,
+        };
+        self.set_juror_application(voter_user_id, singer_juror_user, &juror_application);
+        self.index_juror_application(voter_user_id, singer_juror_user);
+        self.juror_stake_tree_store(singer_juror_user, voter_user_id, stake);
+        self.charge_storage(&account_id, initial_storage);
+        Event::JurorApply {
+            voter_user_id: voter_user_id.into(),
+            juror_user_id: singer_juror_user.into(),
+            stake: stake.into(),
+        }
+        .emit();
+    }
+
+    /// Vouches for the voter application with `bond`, which is locked till the application is
+    /// settled. The bond is returned if the voter is accepted, and slashed if the final ruling,
+    /// after any appeal, rejects the voter. Only validated voters can vouch, and only while
+    /// jurors can apply, so the jurors can see the vouches before they stake.
+    pub fn vouch_for_voter(&mut self, voter_username: AccountId, bond: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let account_id = env::signer_account_id();
+        let voucher = self.get_user_id(&account_id);
         let voter_user_id = self.get_user_id(&voter_username);
-        self.user_juror_stakes_store(
-            account_id.clone(),
-            singer_juror_user.clone(),
-            voter_user_id.clone(),
-            stake.clone(),
-        );
-        self.user_juror_stakes_clone_store(
-            singer_juror_user.clone(),
-            voter_user_id.clone(),
-            stake.clone(),
-        );
+        if voucher == voter_user_id {
+            panic!("You can't vouch for yourself");
+        }
+        if !self.is_validated_voter(account_id.clone()) {
+            panic!("Only validated voters can vouch");
+        }
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::JurorApplication);
+        let mut vouches = match self.voter_vouches.get(&voter_user_id) {
+            Some(vouches) => vouches,
+            None => {
+                let vouchesidstring = format!(
+                    "vouchesvoterid{}uniqueid{}",
+                    voter_user_id, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                Vector::new(vouchesidstring.into_bytes())
+            }
+        };
+        if vouches.iter().any(|vouch| vouch.voucher == voucher) {
+            panic!("You have already vouched for the voter");
+        }
+        self.lock_balance(&account_id, bond);
+        let vouch = Vouch {
+            voucher,
+            bond: bond.into(),
+        };
+        vouches.push(&vouch);
+        self.voter_vouches.insert(&voter_user_id, &vouches);
+        self.charge_storage(&account_id, initial_storage);
+        Event::VoterVouch {
+            voter_user_id: voter_user_id.into(),
+            voucher: voucher.into(),
+            bond: bond.into(),
+        }
+        .emit();
+    }
+
+    /// Returns the vouches for the voter application in the order they were made.
+    pub fn get_voter_vouches(&self, voter_user_id: u128) -> Vec<Vouch> {
+        match self.voter_vouches.get(&voter_user_id) {
+            Some(vouches) => vouches.to_vec(),
+            None => vec![],
+        }
+    }
+
+    /// Returns why the juror can't be a juror for the voter, None if there is no conflict
+    /// of interest. Checked when applying and again when drawing the jurors.
+    fn conflict_of_interest(
+        &self,
+        voter_user_id: u128,
+        juror_user_id: u128,
+    ) -> Option<&'static str> {
+        let applicant_user_id = self.get_applicant_user_id(voter_user_id);
+        if applicant_user_id == juror_user_id {
+            return Some("You can't be a juror for your own voter application");
+        }
+        if let Some(drawn_at) = self.get_last_jury_service(applicant_user_id, juror_user_id) {
+            let cooldown_end =
+                drawn_at.saturating_add(self.validation_config.reciprocal_jury_cooldown);
+            if env::block_timestamp() < cooldown_end {
+                return Some(
+                    "The voter was drawn as your juror within the reciprocal jury cooldown",
+                );
+            }
+        }
+        if self.is_declared_relationship(applicant_user_id, juror_user_id) {
+            return Some("You have declared a relationship with the voter, or the voter with you");
+        }
+        if self
+            .get_voter_vouches(voter_user_id)
+            .iter()
+            .any(|vouch| vouch.voucher == juror_user_id)
+        {
+            return Some("You have vouched for the voter");
+        }
+        None
+    }
+
+    /// Returns the time the juror was last drawn for the voter.
+    pub fn get_last_jury_service(&self, juror_user_id: u128, voter_user_id: u128) -> Option<u64> {
+        self.jury_service
+            .get(&juror_user_id)
+            .and_then(|jury_service| jury_service.get(&voter_user_id))
+    }
+
+    fn record_jury_service(&mut self, juror_user_id: u128, voter_user_id: u128) {
+        let mut jury_service = match self.jury_service.get(&juror_user_id) {
+            Some(jury_service) => jury_service,
+            None => {
+                let juryserviceidstring = format!(
+                    "juryservicejurorid{}uniqueid{}",
+                    juror_user_id, self.juror_vote_unique_id
+                );
+                self.juror_vote_unique_id += 1;
+                LookupMap::new(juryserviceidstring.into_bytes())
+            }
+        };
+        jury_service.insert(&voter_user_id, &env::block_timestamp());
+        self.jury_service.insert(&juror_user_id, &jury_service);
+    }
+
+    /// Declares that the caller is related to `account_id`, so neither can be a juror for
+    /// the other. A declaration can't be removed, and it can't be made while the voter
+    /// application of the caller is in progress.
+    pub fn declare_relationship(&mut self, account_id: AccountId) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let signer_account_id = env::signer_account_id();
+        let user_id = self.get_user_id(&signer_account_id);
+        let related_user_id = self.get_user_id(&account_id);
+        if user_id == related_user_id {
+            panic!("You can't declare a relationship with yourself");
+        }
+        match self.get_voter_application_phase(user_id.into()) {
+            ApplicationPhase::NotStarted | ApplicationPhase::Settled => {}
+            _ => panic!("Voter application is in progress"),
+        }
+        let mut relationships = match self.declared_relationships.get(&user_id) {
+            Some(relationships) => relationships,
+            None => {
+                let relationshipsidstring = format!(
+                    "relationshipsuserid{}uniqueid{}",
+                    user_id, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                UnorderedSet::new(relationshipsidstring.into_bytes())
+            }
+        };
+        relationships.insert(&related_user_id);
+        self.declared_relationships.insert(&user_id, &relationships);
+        self.charge_storage(&signer_account_id, initial_storage);
+        Event::RelationshipDeclare {
+            user_id: user_id.into(),
+            related_user_id: related_user_id.into(),
+        }
+        .emit();
+    }
+
+    /// Returns the user ids of the accounts the user declared a relationship with.
+    pub fn get_declared_relationships(&self, user_id: u128) -> Vec<u128> {
+        match self.declared_relationships.get(&user_id) {
+            Some(relationships) => relationships.to_vec(),
+            None => vec![],
+        }
     }
-    fn user_juror_stakes_store(
+
+    fn is_declared_relationship(&self, user_id: u128, other_user_id: u128) -> bool {
+        let declared = |user_id: u128, related_user_id: u128| {
+            self.declared_relationships
+                .get(&user_id)
+                .is_some_and(|relationships| relationships.contains(&related_user_id))
+        };
+        declared(user_id, other_user_id) || declared(other_user_id, user_id)
+    }
+
+    /// Adds the juror to the applicants of the voter and the voter to the applications
+    /// of the juror, used by the paginated views.
+    fn index_juror_application(&mut self, voter_user_id: u128, juror_user_id: u128) {
+        let mut juror_applicants = match self.juror_applicants.get(&voter_user_id) {
+            Some(juror_applicants) => juror_applicants,
+            None => {
+                let applicantsidstring = format!(
+                    "jurorapplicantsvoterid{}uniqueid{}",
+                    voter_user_id, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                Vector::new(applicantsidstring.into_bytes())
+            }
+        };
+        juror_applicants.push(&juror_user_id);
+        self.juror_applicants
+            .insert(&voter_user_id, &juror_applicants);
+        let mut applied_for = match self.juror_applied_for.get(&juror_user_id) {
+            Some(applied_for) => applied_for,
+            None => {
+                let appliedforidstring = format!(
+                    "appliedforjurorid{}uniqueid{}",
+                    juror_user_id, self.juror_stake_unique_id
+                );
+                self.juror_stake_unique_id += 1;
+                UnorderedSet::new(appliedforidstring.into_bytes())
+            }
+        };
+        applied_for.insert(&voter_user_id);
+        self.juror_applied_for.insert(&juror_user_id, &applied_for);
+    }
+
+    /// Returns the application of the juror for the voter.
+    pub fn get_juror_application(
+        &self,
+        voter_user_id: U128,
+        juror_user_id: u128,
+    ) -> Option<JurorApplication> {
+        let voter_user_id = voter_user_id.0;
+        self.juror_applications
+            .get(&voter_user_id)
+            .and_then(|juror_applications| juror_applications.get(&juror_user_id))
+    }
+
+    fn set_juror_application(
         &mut self,
-        account_id: String,
-        singer_juror_user: u128,
         voter_user_id: u128,
-        stake: u128,
+        juror_user_id: u128,
+        juror_application: &JurorApplication,
     ) {
-        let user_juror_stakes_option = self.user_juror_stakes.get(&voter_user_id);
-        match user_juror_stakes_option {
-            Some(mut stake_entries) => {
-                let stake_entries_option = stake_entries.get(&singer_juror_user);
-                match stake_entries_option {
-                    Some(stake) => {
-                        if stake > 0 {
-                            panic!("You have already staked")
-                        } else {
-                            stake_entries.insert(&singer_juror_user, &stake);
-                            self.burn(&account_id, stake);
-                            self.user_juror_stakes
-                                .insert(&voter_user_id, &stake_entries);
-                        }
-                    }
-                    None => {
-                        stake_entries.insert(&singer_juror_user, &stake);
-                        self.burn(&account_id, stake);
-                        self.user_juror_stakes
-                            .insert(&voter_user_id, &stake_entries);
-                    }
-                }
-            }
+        let mut juror_applications = match self.juror_applications.get(&voter_user_id) {
+            Some(juror_applications) => juror_applications,
             None => {
-                let stakeidstring = format!(
-                    "stakevoterid{}uniqueid{}",
+                let applicationidstring = format!(
+                    "jurorapplicationvoterid{}uniqueid{}",
                     voter_user_id, self.juror_stake_unique_id
                 );
-                let stakeid = stakeidstring.to_string().into_bytes();
-                let mut stake_entries = LookupMap::new(stakeid);
-                stake_entries.insert(&singer_juror_user, &stake);
-                self.burn(&account_id, stake);
-                self.user_juror_stakes
-                    .insert(&voter_user_id, &stake_entries);
+                self.juror_stake_unique_id += 1;
+                LookupMap::new(applicationidstring.into_bytes())
             }
-        }
+        };
+        juror_applications.insert(&juror_user_id, juror_application);
+        self.juror_applications
+            .insert(&voter_user_id, &juror_applications);
     }
 
-    fn user_juror_stakes_clone_store(
+    fn juror_stake_tree_store(
         &mut self,
         singer_juror_user: u128,
         voter_user_id: u128,
         stake: u128,
     ) {
-        let user_juror_stakes_option = self.user_juror_stakes_clone.get(&voter_user_id);
-        match user_juror_stakes_option {
-            Some(mut stake_entries) => {
-                let stake_entries_option = stake_entries.get(&singer_juror_user);
-                match stake_entries_option {
-                    Some(stake) => {
-                        if stake > 0 {
-                            panic!("You have already staked")
-                        } else {
-                            stake_entries.insert(&singer_juror_user, &stake);
-                            self.user_juror_stakes_clone
-                                .insert(&voter_user_id, &stake_entries);
-                        }
-                    }
-                    None => {
-                        stake_entries.insert(&singer_juror_user, &stake);
-                        self.user_juror_stakes_clone
-                            .insert(&voter_user_id, &stake_entries);
-                    }
-                }
-            }
+        let mut juror_stake_tree = match self.juror_stake_trees.get(&voter_user_id) {
+            Some(juror_stake_tree) => juror_stake_tree,
             None => {
                 let stakeidstring = format!(
-                    "stakevoteridclone{}uniqueid{}",
+                    "stakevotertree{}uniqueid{}",
                     voter_user_id, self.juror_stake_unique_id
                 );
                 self.juror_stake_unique_id += 1;
-                let stakeid = stakeidstring.to_string().into_bytes();
-                let mut stake_entries = TreeMap::new(stakeid);
-                stake_entries.insert(&singer_juror_user, &stake);
-                self.user_juror_stakes_clone
-                    .insert(&voter_user_id, &stake_entries);
+                SortitionSumTree::new(JUROR_STAKE_TREE_K, stakeidstring)
             }
-        }
+        };
+        // With a reputation weighted draw, the weight is fixed by the reputation the juror has
+        // when applying, so the draw stays a single sum tree draw per juror. A reputation
+        // earned later counts for the next applications of the juror.
+        let weight = if self.validation_config.reputation_weighted_draw {
+            let weight_percent = self
+                .get_juror_reputation(singer_juror_user)
+                .draw_weight_percent();
+            std::cmp::max(stake * weight_percent / 100, 1)
+        } else {
+            stake
+        };
+        juror_stake_tree.set(weight, singer_juror_user.to_string());
+        self.juror_stake_trees
+            .insert(&voter_user_id, &juror_stake_tree);
     }
 
+    /// Draws the jurors of the current round, the first round draws from all juror applicants
+    /// and each appeal round draws from the applicants who were not drawn before.
     pub fn draw_jurors(&mut self, voter_username: AccountId) {
         let voter_user_id = self.get_user_id(&voter_username);
-        let selected_juror_option = self.selected_juror.get(&voter_user_id);
-        match selected_juror_option {
-            Some(jurysetentries) => {
-                self.draw_jurors_function(voter_user_id, jurysetentries);
-            }
+        self.internal_draw_jurors(voter_user_id);
+    }
+
+    pub(crate) fn internal_draw_jurors(&mut self, voter_user_id: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::Draw);
+        let round = self.get_appeal_round(voter_user_id.into());
+        let mut jury_rounds = match self.selected_juror.get(&voter_user_id) {
+            Some(jury_rounds) => jury_rounds,
             None => {
-                let jurysetidstring = format!("jurysetid{}", voter_username);
-                let jurysetid = jurysetidstring.to_string().into_bytes();
-                let jurysetentries = LookupSet::new(jurysetid);
-                self.draw_jurors_function(voter_user_id, jurysetentries);
+                let juryroundsidstring = format!(
+                    "juryroundsvoterid{}uniqueid{}",
+                    voter_user_id, self.juror_vote_unique_id
+                );
+                self.juror_vote_unique_id += 1;
+                Vector::new(juryroundsidstring.into_bytes())
             }
+        };
+        if jury_rounds.len() > round {
+            panic!("Jurors are already drawn");
+        }
+        let jurysetidstring = format!(
+            "jurysetid{}uniqueid{}",
+            voter_user_id, self.juror_vote_unique_id
+        );
+        self.juror_vote_unique_id += 1;
+        let jurysetid = jurysetidstring.to_string().into_bytes();
+        let jurysetentries = UnorderedSet::new(jurysetid);
+        let (jurysetentries, credited_storage) =
+            self.draw_jurors_function(voter_user_id, round, jurysetentries);
+        jury_rounds.push(&jurysetentries);
+        self.selected_juror.insert(&voter_user_id, &jury_rounds);
+        // The storage credited to the drawn jurors isn't credited to the caller again
+        self.charge_storage(
+            &env::signer_account_id(),
+            initial_storage - credited_storage,
+        );
+        Event::JurorDraw {
+            voter_user_id: voter_user_id.into(),
+            round,
+            juror_user_ids: jurysetentries
+                .iter()
+                .map(|juror_user_id| juror_user_id.into())
+                .collect(),
         }
+        .emit();
     }
-    fn draw_jurors_function(&mut self, voter_user_id: u128, mut jurysetentries: LookupSet<u128>) {
-        let user_juror_stakes_clone_option = self.user_juror_stakes_clone.get(&voter_user_id);
-        match user_juror_stakes_clone_option {
-            Some(mut juries_stakes) => {
-                let items = juries_stakes.to_vec();
-                println!(">>>>>>>>Juries{:?}<<<<<<<<<<<", items);
+    /// Returns the jury of the round and the storage credited to the drawn jurors for their
+    /// leaves of the stake tree.
+    fn draw_jurors_function(
+        &mut self,
+        voter_user_id: u128,
+        round: u64,
+        mut jurysetentries: UnorderedSet<u128>,
+    ) -> (UnorderedSet<u128>, StorageUsage) {
+        let juror_stake_tree_option = self.juror_stake_trees.get(&voter_user_id);
+        match juror_stake_tree_option {
+            Some(mut juror_stake_tree) => {
+                let mut applicants = juror_stake_tree.len();
+                let applicant_user_id = self.get_applicant_user_id(voter_user_id);
+                let mut length = self.round_jury_size(voter_user_id, round, applicants);
                 let random_vec = env::random_seed();
-                let mut rng = get_rng(random_vec);
-                let mut dist2 = WeightedIndex::new(items.iter().map(|item| item.1)).unwrap();
-                let mut length = items.len();
-                if length > 20 {
-                    length = 20;
-                }
+                let mut rng = get_rng(random_vec.clone());
+                let (candidates, weights) = juror_stake_tree
+                    .to_draw_order_vec()
+                    .into_iter()
+                    .map(|(id, weight)| (U128(id.parse().unwrap()), U128(weight)))
+                    .unzip();
+                let mut draws = Vec::new();
+                let mut credited_storage = 0;
 
-                for _ in 0..length {
-                    let index = dist2.sample(&mut rng);
-                    // println!("{}", index);
-                    let drawindex = items[index].0;
-                    println!("{:?}", drawindex);
-                    juries_stakes.remove(&drawindex);
+                // Drawn jurors are removed from the tree, so each juror is drawn only once. A
+                // drawn juror who got a conflict of interest with the voter after applying is
+                // withdrawn instead, and the jury size is recomputed without it.
+                while jurysetentries.len() < length {
+                    let draw_number = rng.gen::<u128>();
+                    let drawid = juror_stake_tree.draw(draw_number).unwrap();
+                    let drawindex: u128 = drawid.parse().unwrap();
+                    let conflicted = self
+                        .conflict_of_interest(voter_user_id, drawindex)
+                        .is_some();
+                    draws.push(JurorDraw {
+                        draw_number: draw_number.into(),
+                        juror_user_id: drawindex.into(),
+                        weight: juror_stake_tree.stake_of(&drawid).into(),
+                        withdrawn: conflicted,
+                    });
+                    // The drawn juror paid for its leaf when applying
+                    let initial_storage = env::storage_usage();
+                    juror_stake_tree.set(0, drawid.clone());
+                    credited_storage += initial_storage.saturating_sub(env::storage_usage());
+                    let juror_account_id = self.get_user_account_id(drawindex);
+                    self.credit_storage(&juror_account_id, initial_storage);
+                    let mut juror_application = self
+                        .get_juror_application(voter_user_id.into(), drawindex)
+                        .unwrap();
+                    if conflicted {
+                        juror_application.status = JurorApplicationStatus::Withdrawn;
+                        self.set_juror_application(voter_user_id, drawindex, &juror_application);
+                        self.unlock_balance(&juror_account_id, juror_application.stake.0);
+                        Event::JurorWithdraw {
+                            voter_user_id: voter_user_id.into(),
+                            juror_user_id: drawindex.into(),
+                            stake: juror_application.stake,
+                        }
+                        .emit();
+                        applicants -= 1;
+                        length = self.round_jury_size(voter_user_id, round, applicants);
+                        continue;
+                    }
                     jurysetentries.insert(&drawindex);
-                    let _d = dist2.update_weights(&[(index, &0)]);
+                    juror_application.status = JurorApplicationStatus::Drawn;
+                    juror_application.round = Some(round);
+                    self.set_juror_application(voter_user_id, drawindex, &juror_application);
+                    self.record_jury_service(drawindex, applicant_user_id);
                 }
-                self.user_juror_stakes_clone
-                    .insert(&voter_user_id, &juries_stakes);
-                self.selected_juror.insert(&voter_user_id, &jurysetentries);
+                self.juror_stake_trees
+                    .insert(&voter_user_id, &juror_stake_tree);
+                let transcript = DrawTranscript {
+                    round,
+                    seed: random_vec,
+                    candidates,
+                    weights,
+                    draws,
+                    drawn_by: env::signer_account_id(),
+                };
+                self.push_draw_transcript(voter_user_id, &transcript);
+                (jurysetentries, credited_storage)
             }
             None => {
                 panic!("There are no juries");
@@ -241,8 +838,530 @@ impl FungibleToken {
         }
     }
 
-    pub fn get_selected_jurors(&self, voter_user_id: u128) -> LookupSet<u128> {
-        let selected_juror_option = self.selected_juror.get(&voter_user_id);
+    fn push_draw_transcript(&mut self, voter_user_id: u128, transcript: &DrawTranscript) {
+        let mut transcripts = match self.draw_transcripts.get(&voter_user_id) {
+            Some(transcripts) => transcripts,
+            None => {
+                let transcriptsidstring = format!(
+                    "drawtranscriptsvoterid{}uniqueid{}",
+                    voter_user_id, self.juror_vote_unique_id
+                );
+                self.juror_vote_unique_id += 1;
+                Vector::new(transcriptsidstring.into_bytes())
+            }
+        };
+        transcripts.push(transcript);
+        self.draw_transcripts.insert(&voter_user_id, &transcripts);
+    }
+
+    /// Returns the transcript of the juror draw of the round, which can be checked with
+    /// the draw-verifier crate.
+    pub fn get_draw_transcript(&self, voter_user_id: U128, round: u64) -> Option<DrawTranscript> {
+        let voter_user_id = voter_user_id.0;
+        self.draw_transcripts
+            .get(&voter_user_id)
+            .and_then(|transcripts| transcripts.get(round))
+    }
+
+    /// Returns the stake of a juror applicant who was not drawn for the voter, once the
+    /// first round of jurors is drawn. A withdrawn applicant can't be drawn in appeal rounds.
+    pub fn withdraw_juror_stake(&mut self, voter_username: AccountId) {
+        let voter_user_id = self.get_user_id(&voter_username);
+        self.internal_withdraw_juror_stake(voter_user_id);
+    }
+
+    pub(crate) fn internal_withdraw_juror_stake(&mut self, voter_user_id: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let account_id = env::signer_account_id();
+        let juror_user_id = self.get_user_id(&account_id);
+        if self.selected_juror.get(&voter_user_id).is_none() {
+            panic!("Jurors are not drawn for the voter");
+        }
+        let mut juror_application =
+            match self.get_juror_application(voter_user_id.into(), juror_user_id) {
+                Some(juror_application) => juror_application,
+                None => panic!("You have not applied as a juror for the voter"),
+            };
+        match juror_application.status {
+            JurorApplicationStatus::Applied => {}
+            JurorApplicationStatus::Withdrawn => panic!("Juror stake is already withdrawn"),
+            _ => panic!("You are drawn as a juror for the voter"),
+        }
+        let mut juror_stake_tree = self.juror_stake_trees.get(&voter_user_id).unwrap();
+        juror_stake_tree.set(0, juror_user_id.to_string());
+        self.juror_stake_trees
+            .insert(&voter_user_id, &juror_stake_tree);
+        juror_application.status = JurorApplicationStatus::Withdrawn;
+        self.set_juror_application(voter_user_id, juror_user_id, &juror_application);
+        self.unlock_balance(&account_id, juror_application.stake.0);
+        self.charge_storage(&account_id, initial_storage);
+        Event::JurorWithdraw {
+            voter_user_id: voter_user_id.into(),
+            juror_user_id: juror_user_id.into(),
+            stake: juror_application.stake,
+        }
+        .emit();
+    }
+
+    pub fn is_juror_stake_withdrawn(&self, voter_user_id: U128, juror_user_id: u128) -> bool {
+        let voter_user_id = voter_user_id.0;
+        match self.get_juror_application(voter_user_id.into(), juror_user_id) {
+            Some(juror_application) => {
+                juror_application.status == JurorApplicationStatus::Withdrawn
+            }
+            None => false,
+        }
+    }
+
+    /// Number of jurors to draw from `applicants` juror applicants, which is
+    /// `jury_size_percent` of the applicants but not less than `min_jurors`.
+    fn jury_size(&self, applicants: u64) -> u64 {
+        let min_jurors = self.validation_config.min_jurors;
+        if applicants < min_jurors {
+            panic!(
+                "Not enough jurors have applied, {} applied but at least {} are required",
+                applicants, min_jurors
+            );
+        }
+        let percent_size = (applicants * self.validation_config.jury_size_percent).div_ceil(100);
+        if percent_size < min_jurors {
+            min_jurors
+        } else {
+            percent_size
+        }
+    }
+
+    /// Number of jurors to draw for the `round` from `applicants` juror applicants.
+    fn round_jury_size(&self, voter_user_id: u128, round: u64, applicants: u64) -> u64 {
+        if round == 0 {
+            self.jury_size(applicants)
+        } else {
+            self.appeal_jury_size(voter_user_id, round, applicants)
+        }
+    }
+
+    /// Number of jurors to draw for the appeal `round`, which is double the jurors of the
+    /// previous round plus one, from `applicants` juror applicants who were not drawn before.
+    fn appeal_jury_size(&self, voter_user_id: u128, round: u64, applicants: u64) -> u64 {
+        let previous_jury_size = self
+            .get_round_selected_jurors(voter_user_id, round - 1)
+            .len();
+        let jury_size = previous_jury_size * 2 + 1;
+        if applicants < jury_size {
+            panic!(
+                "Not enough jurors have applied for the appeal, {} remaining but {} are required",
+                applicants, jury_size
+            );
+        }
+        jury_size
+    }
+
+    /// Returns the reward a voter earns on `stake` if the application is accepted.
+    pub fn get_voter_reward(&self, stake: U128) -> U128 {
+        self.validation_config
+            .voter_reward_curve
+            .reward(stake.into())
+            .into()
+    }
+
+    pub fn get_validation_config(&self) -> ValidationConfig {
+        self.validation_config.clone()
+    }
+
+    /// Selected juror commits the vote for the voter application.
+    /// `vote_commit` is the Keccak256 hex hash of the juror's account id followed by the vote,
+    /// where the vote starts with 1 (accept) or 0 (reject) followed by a secret, e.g.
+    /// "juror.near1mysecret". The account id binds the commit to the juror, so another juror
+    /// can't copy the commit and replay the reveal.
+    pub fn commit_juror_vote(&mut self, voter_username: AccountId, vote_commit: String) {
+        let voter_user_id = self.get_user_id(&voter_username);
+        self.internal_commit_juror_vote(voter_user_id, vote_commit);
+    }
+
+    pub(crate) fn internal_commit_juror_vote(&mut self, voter_user_id: u128, vote_commit: String) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let account_id = env::signer_account_id();
+        let juror_user_id = self.get_user_id(&account_id);
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::Commit);
+        let mut juror_application = self.assert_selected_juror(voter_user_id, juror_user_id);
+        if juror_application.vote_commit.is_some() {
+            panic!("Vote commit is already done");
+        }
+        juror_application.vote_commit = Some(vote_commit);
+        juror_application.status = JurorApplicationStatus::Voted;
+        self.set_juror_application(voter_user_id, juror_user_id, &juror_application);
+        self.charge_storage(&account_id, initial_storage);
+        Event::VoteCommit {
+            voter_user_id: voter_user_id.into(),
+            juror_user_id: juror_user_id.into(),
+            round: juror_application.round.unwrap(),
+        }
+        .emit();
+    }
+
+    /// Selected juror reveals the vote committed with `commit_juror_vote`.
+    pub fn reveal_juror_vote(&mut self, voter_username: AccountId, vote: String) {
+        let voter_user_id = self.get_user_id(&voter_username);
+        self.internal_reveal_juror_vote(voter_user_id, vote);
+    }
+
+    pub(crate) fn internal_reveal_juror_vote(&mut self, voter_user_id: u128, vote: String) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let account_id = env::signer_account_id();
+        let juror_user_id = self.get_user_id(&account_id);
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::Reveal);
+        let mut juror_application = self.assert_selected_juror(voter_user_id, juror_user_id);
+        let vote_commit = match juror_application.vote_commit.clone() {
+            Some(vote_commit) => vote_commit,
+            None => panic!("Vote with this commit was not cast"),
+        };
+        let mut hasher = Keccak256::new();
+        hasher.update(account_id.as_bytes());
+        hasher.update(vote.as_bytes());
+        let result = hasher.finalize();
+        let vote_hex = format!("{:x}", result);
+        if vote_commit != vote_hex {
+            panic!("Vote hash doesn't match the vote commit");
+        }
+        let decision = match vote.chars().next() {
+            Some('1') => true,
+            Some('0') => false,
+            _ => panic!("Vote should start with 1 (accept) or 0 (reject)"),
+        };
+        if juror_application.vote.is_some() {
+            panic!("The vote was already revealed");
+        }
+        juror_application.vote = Some(decision);
+        juror_application.status = JurorApplicationStatus::Revealed;
+        self.set_juror_application(voter_user_id, juror_user_id, &juror_application);
+        self.charge_storage(&account_id, initial_storage);
+        Event::VoteReveal {
+            voter_user_id: voter_user_id.into(),
+            juror_user_id: juror_user_id.into(),
+            round: juror_application.round.unwrap(),
+            vote: decision,
+        }
+        .emit();
+    }
+
+    /// Appeals the ruling of the current round by paying the appeal fee. The voter and the jurors
+    /// who staked for the voter can appeal after the votes are revealed. A new round starts,
+    /// where a jury of double the previous jurors plus one is drawn from the applicants
+    /// who were not drawn before.
+    pub fn appeal_voter_application(&mut self, voter_username: AccountId) {
+        let voter_user_id = self.get_user_id(&voter_username);
+        self.internal_appeal_voter_application(voter_user_id);
+    }
+
+    pub(crate) fn internal_appeal_voter_application(&mut self, voter_user_id: u128) {
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        let account_id = env::signer_account_id();
+        let appellant = self.get_user_id(&account_id);
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::Appeal);
+        let juror_staked = match self.get_juror_application(voter_user_id.into(), appellant) {
+            Some(juror_application) => {
+                juror_application.status != JurorApplicationStatus::Withdrawn
+            }
+            None => false,
+        };
+        if appellant != self.get_applicant_user_id(voter_user_id) && !juror_staked {
+            panic!("Only the voter or the jurors who staked for the voter can appeal");
+        }
+        let round = self.get_appeal_round(voter_user_id.into());
+        if self
+            .get_round_selected_jurors(voter_user_id, round)
+            .is_empty()
+        {
+            panic!("Jurors are not drawn for the voter");
+        }
+        let remaining_applicants = match self.juror_stake_trees.get(&voter_user_id) {
+            Some(juror_stake_tree) => juror_stake_tree.len(),
+            None => 0,
+        };
+        self.appeal_jury_size(voter_user_id, round + 1, remaining_applicants);
+
+        let fee = self.validation_config.appeal_fee.0;
+        if fee > 0 {
+            self.lock_balance(&account_id, fee);
+        }
+        let appeal = Appeal {
+            appellant,
+            fee: fee.into(),
+            appealed_ruling: self.round_ruling(voter_user_id, round),
+        };
+        let mut appeals = match self.voter_appeals.get(&voter_user_id) {
+            Some(appeals) => appeals,
+            None => {
+                let appealsidstring = format!(
+                    "appealsvoterid{}uniqueid{}",
+                    voter_user_id, self.juror_vote_unique_id
+                );
+                self.juror_vote_unique_id += 1;
+                Vector::new(appealsidstring.into_bytes())
+            }
+        };
+        appeals.push(&appeal);
+        self.voter_appeals.insert(&voter_user_id, &appeals);
+
+        let mut timeline = self.get_unpaused_timeline(voter_user_id);
+        timeline.draw_end = self.unpaused_timestamp() + DRAW_PERIOD;
+        timeline.commit_end = timeline.draw_end + COMMIT_PERIOD;
+        timeline.reveal_end = timeline.commit_end + REVEAL_PERIOD;
+        timeline.appeal_end = timeline.reveal_end + APPEAL_PERIOD;
+        self.voter_timeline.insert(&voter_user_id, &timeline);
+        self.charge_storage(&account_id, initial_storage);
+        Event::VoterApplicationAppeal {
+            voter_user_id: voter_user_id.into(),
+            appellant: appellant.into(),
+            round: round + 1,
+            fee: fee.into(),
+        }
+        .emit();
+    }
+
+    /// Ruling of a round, `Some(true)` to accept if the majority of the revealed votes of the
+    /// round's jurors accept the voter, `Some(false)` to reject if the majority reject the
+    /// voter, and None if no vote is revealed or the votes are tied.
+    fn round_ruling(&self, voter_user_id: u128, round: u64) -> Option<bool> {
+        let jurors = self
+            .get_round_selected_jurors(voter_user_id, round)
+            .to_vec();
+        let mut accept_count = 0;
+        let mut revealed_count = 0;
+        for juror_user_id in jurors.iter() {
+            if let Some(decision) = self.get_juror_vote(voter_user_id.into(), *juror_user_id) {
+                revealed_count += 1;
+                if decision {
+                    accept_count += 1;
+                }
+            }
+        }
+        match (accept_count * 2).cmp(&revealed_count) {
+            std::cmp::Ordering::Greater => Some(true),
+            std::cmp::Ordering::Less => Some(false),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Settles the voter application once the appeal period of the last round is over.
+    /// The ruling of the last drawn round is final. The ruling is accept if the majority of its
+    /// revealed votes accept the voter, in which case the voter gets back the stake along with
+    /// the reward from the voter reward curve and `kyc_done` is set, and reject if the majority
+    /// reject the voter, in which case the voter's stake is burned and `kyc_rejected` is set.
+    /// Jurors of all rounds who voted with the final ruling get back their stake, the juror
+    /// an equal share of the juror incentive `JUROR_INCENTIVE`, of the slashed stakes and of
+    /// the fees of the failed appeals.
+    /// Jurors who voted against the final ruling or didn't reveal their vote lose their stake.
+    /// If the last round gives no ruling, because no vote is revealed or the votes are tied,
+    /// the voter and the vouchers get back their stakes without a reward, the jurors who
+    /// revealed their vote get back their stake and share the slashed stakes without the
+    /// incentive, and the application is cleared so the voter can stake again.
+    /// The reputation of each juror is updated with the outcome of its vote.
+    /// Appellants whose appeal changed the ruling, or whose appeal round was never drawn, get
+    /// back the appeal fee. Stakes and appeal fees stay in the locked balance until
+    /// settlement, so only the rewards are minted.
+    /// If no jury was drawn, because too few jurors applied or nobody drew them in the draw
+    /// period, the application is cancelled with `cancel_voter_application`.
+    pub fn settle_voter_application(&mut self, voter_user_id: U128) {
+        let voter_user_id = voter_user_id.0;
+        self.assert_not_paused();
+        let initial_storage = env::storage_usage();
+        if self.voter_ruling.get(&voter_user_id).is_some() {
+            panic!("Voter application is already settled");
+        }
+        self.assert_voter_application_phase(voter_user_id, ApplicationPhase::Settlement);
+        let jury_rounds = match self.selected_juror.get(&voter_user_id) {
+            Some(jury_rounds) => jury_rounds.to_vec(),
+            None => {
+                self.charge_storage(&env::signer_account_id(), initial_storage);
+                self.cancel_voter_application(voter_user_id);
+                return;
+            }
+        };
+        let final_round = jury_rounds.len() as u64 - 1;
+        let ruling = self.round_ruling(voter_user_id, final_round);
+        let jurors: Vec<u128> = jury_rounds
+            .iter()
+            .flat_map(|jurysetentries| jurysetentries.to_vec())
+            .collect();
+
+        let mut coherent_jurors = Vec::new();
+        let mut forfeited: u128 = 0;
+        for juror_user_id in jurors.iter() {
+            let mut juror_application = self
+                .get_juror_application(voter_user_id.into(), *juror_user_id)
+                .unwrap();
+            let stake = juror_application.stake.0;
+            let vote = juror_application.vote;
+            juror_application.status = JurorApplicationStatus::Settled;
+            self.set_juror_application(voter_user_id, *juror_user_id, &juror_application);
+            let mut reputation = self.get_juror_reputation(*juror_user_id);
+            reputation.rounds_drawn += 1;
+            if vote.is_some() {
+                reputation.rounds_revealed += 1;
+            }
+            let juror_account_id = self.get_user_account_id(*juror_user_id);
+            let coherent = match ruling {
+                Some(_) => vote == ruling,
+                None => vote.is_some(),
+            };
+            if coherent {
+                if ruling.is_some() {
+                    reputation.coherent_votes += 1;
+                }
+                self.unlock_balance(&juror_account_id, stake);
+                coherent_jurors.push(juror_account_id);
+            } else {
+                reputation.slashes += 1;
+                self.forfeit_locked_balance(&juror_account_id, stake);
+                forfeited += stake;
+            }
+            self.juror_reputation.insert(juror_user_id, &reputation);
+        }
+        for (appeal_index, appeal) in self
+            .get_voter_appeals(voter_user_id.into())
+            .iter()
+            .enumerate()
+        {
+            let appellant_account_id = self.get_user_account_id(appeal.appellant);
+            let appeal_round_drawn = (appeal_index as u64) < final_round;
+            if !appeal_round_drawn || appeal.appealed_ruling != ruling {
+                self.unlock_balance(&appellant_account_id, appeal.fee.0);
+            } else {
+                self.forfeit_locked_balance(&appellant_account_id, appeal.fee.0);
+                forfeited += appeal.fee.0;
+            }
+        }
+
+        // The forfeited tokens are burned and equal shares are minted to the coherent
+        // jurors, the remainder that can't be shared stays burned. The juror incentive is
+        // shared the same way, its remainder isn't minted
+        if !coherent_jurors.is_empty() {
+            let forfeited_share = forfeited / coherent_jurors.len() as u128;
+            let incentive_share = match ruling {
+                Some(_) => JUROR_INCENTIVE / coherent_jurors.len() as u128,
+                None => 0,
+            };
+            for juror_account_id in coherent_jurors.iter() {
+                if forfeited_share > 0 {
+                    self.credit_forfeited(juror_account_id, forfeited_share);
+                }
+                if incentive_share > 0 {
+                    self._mint(juror_account_id, incentive_share);
+                }
+            }
+        }
+
+        let voter_stake = self.get_voter_stake(voter_user_id.into());
+        let voter_account_id = self.get_user_account_id(self.get_applicant_user_id(voter_user_id));
+        match ruling {
+            Some(true) => {
+                self.unlock_balance(&voter_account_id, voter_stake);
+                let voter_reward = self
+                    .validation_config
+                    .voter_reward_curve
+                    .reward(voter_stake);
+                if voter_reward > 0 {
+                    self._mint(&voter_account_id, voter_reward);
+                }
+            }
+            Some(false) => {
+                self.forfeit_locked_balance(&voter_account_id, voter_stake);
+            }
+            None => self.unlock_balance(&voter_account_id, voter_stake),
+        }
+        for vouch in self.get_voter_vouches(voter_user_id) {
+            let voucher_account_id = self.get_user_account_id(vouch.voucher);
+            if ruling == Some(false) {
+                self.forfeit_locked_balance(&voucher_account_id, vouch.bond.0);
+            } else {
+                self.unlock_balance(&voucher_account_id, vouch.bond.0);
+            }
+        }
+        Event::VoterApplicationSettle {
+            voter_user_id: voter_user_id.into(),
+            accepted: ruling,
+            rounds: final_round + 1,
+        }
+        .emit();
+        let ruling = match ruling {
+            Some(ruling) => ruling,
+            None => {
+                self.charge_storage(&env::signer_account_id(), initial_storage);
+                self.clear_voter_application(voter_user_id);
+                return;
+            }
+        };
+        match self.expertise_applications.get(&voter_user_id) {
+            Some(expertise_application) => {
+                if ruling {
+                    self.insert_department_expert(
+                        &expertise_application.department,
+                        expertise_application.user_id,
+                    );
+                }
+            }
+            None => {
+                let mut voter = self.get_voter_details(voter_user_id);
+                voter.kyc_done = ruling;
+                voter.kyc_rejected = !ruling;
+                self.voter_profile_map.insert(&voter_user_id, &voter);
+            }
+        }
+        let voter_ruling = VoterRuling {
+            accepted: ruling,
+            settled_by: env::signer_account_id(),
+        };
+        self.voter_ruling.insert(&voter_user_id, &voter_ruling);
+        self.charge_storage(&env::signer_account_id(), initial_storage);
+    }
+
+    /// Returns the stakes of the voter, the juror applicants and the vouchers of an application
+    /// no jury was drawn for, and clears the application so the voter can stake again.
+    fn cancel_voter_application(&mut self, voter_user_id: u128) {
+        let voter_account_id = self.get_user_account_id(self.get_applicant_user_id(voter_user_id));
+        self.unlock_balance(
+            &voter_account_id,
+            self.get_voter_stake(voter_user_id.into()),
+        );
+        for vouch in self.get_voter_vouches(voter_user_id) {
+            let voucher_account_id = self.get_user_account_id(vouch.voucher);
+            self.unlock_balance(&voucher_account_id, vouch.bond.0);
+        }
+        // The stakes of the juror applicants are returned when the application is cleared
+        self.clear_voter_application(voter_user_id);
+        Event::VoterApplicationCancel {
+            voter_user_id: voter_user_id.into(),
+        }
+        .emit();
+    }
+
+    /// Returns the application of the juror if the juror is drawn in the current round.
+    fn assert_selected_juror(&self, voter_user_id: u128, juror_user_id: u128) -> JurorApplication {
+        let round = self.get_appeal_round(voter_user_id.into());
+        let round_drawn = match self.selected_juror.get(&voter_user_id) {
+            Some(jury_rounds) => jury_rounds.len() > round,
+            None => false,
+        };
+        if !round_drawn {
+            panic!("Jurors are not drawn for the voter");
+        }
+        match self.get_juror_application(voter_user_id.into(), juror_user_id) {
+            Some(juror_application) if juror_application.round == Some(round) => juror_application,
+            _ => panic!("You are not a selected juror for the voter"),
+        }
+    }
+
+    /// Returns the jurors of the last drawn round.
+    pub(crate) fn get_selected_jurors(&self, voter_user_id: u128) -> UnorderedSet<u128> {
+        let selected_juror_option = self
+            .selected_juror
+            .get(&voter_user_id)
+            .and_then(|jury_rounds| jury_rounds.get(jury_rounds.len().saturating_sub(1)));
         match selected_juror_option {
             Some(jurysetentries) => jurysetentries,
             None => {
@@ -250,16 +1369,165 @@ impl FungibleToken {
             }
         }
     }
-    pub fn get_juror_stakes(&self, voter_user_id: u128, juror_user_id: u128) -> u128 {
-        let juror_list_option = self.user_juror_stakes.get(&voter_user_id);
-        match juror_list_option {
-            Some(juror_list) => {
-                let juror_stake = juror_list.get(&juror_user_id).unwrap();
-                juror_stake
+
+    /// Returns the jurors of the `round`, the first round is 0 and each appeal adds a round.
+    pub(crate) fn get_round_selected_jurors(
+        &self,
+        voter_user_id: u128,
+        round: u64,
+    ) -> UnorderedSet<u128> {
+        let selected_juror_option = self
+            .selected_juror
+            .get(&voter_user_id)
+            .and_then(|jury_rounds| jury_rounds.get(round));
+        match selected_juror_option {
+            Some(jurysetentries) => jurysetentries,
+            None => {
+                panic!("No selected juroros");
             }
+        }
+    }
+
+    /// Returns the current round of the voter application, which is the number of appeals.
+    pub fn get_appeal_round(&self, voter_user_id: U128) -> u64 {
+        let voter_user_id = voter_user_id.0;
+        match self.voter_appeals.get(&voter_user_id) {
+            Some(appeals) => appeals.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_voter_appeals(&self, voter_user_id: U128) -> Vec<Appeal> {
+        let voter_user_id = voter_user_id.0;
+        match self.voter_appeals.get(&voter_user_id) {
+            Some(appeals) => appeals.to_vec(),
+            None => vec![],
+        }
+    }
+
+    pub fn get_juror_stakes(&self, voter_user_id: U128, juror_user_id: u128) -> u128 {
+        let voter_user_id = voter_user_id.0;
+        match self.get_juror_application(voter_user_id.into(), juror_user_id) {
+            Some(juror_application) => juror_application.stake.0,
             None => panic!("No one has staked for the voter"),
         }
     }
+
+    /// Returns the weight the juror applicant is drawn with, 0 once it is drawn or withdrawn.
+    /// With a reputation weighted draw it is the stake multiplied by the reputation weight
+    /// the juror had when applying.
+    pub fn get_juror_draw_weight(&self, voter_user_id: U128, juror_user_id: u128) -> u128 {
+        let voter_user_id = voter_user_id.0;
+        match self.juror_stake_trees.get(&voter_user_id) {
+            Some(juror_stake_tree) => juror_stake_tree.stake_of(&juror_user_id.to_string()),
+            None => 0,
+        }
+    }
+
+    /// Returns the reputation of the juror, all zero if the juror was never drawn in a
+    /// settled voter application.
+    pub fn get_juror_reputation(&self, juror_user_id: u128) -> JurorReputation {
+        self.juror_reputation
+            .get(&juror_user_id)
+            .unwrap_or_default()
+    }
+
+    /// Returns the ruling of the settled voter application, `true` if the voter is accepted.
+    pub fn get_voter_ruling(&self, voter_user_id: U128) -> Option<bool> {
+        let voter_user_id = voter_user_id.0;
+        self.voter_ruling
+            .get(&voter_user_id)
+            .map(|voter_ruling| voter_ruling.accepted)
+    }
+
+    /// Returns the revealed vote of the juror, `true` for accept and `false` for reject.
+    pub fn get_juror_vote(&self, voter_user_id: U128, juror_user_id: u128) -> Option<bool> {
+        let voter_user_id = voter_user_id.0;
+        self.get_juror_application(voter_user_id.into(), juror_user_id)
+            .and_then(|juror_application| juror_application.vote)
+    }
+
+    /// Returns the jurors drawn in the `round` with their applications, starting at
+    /// `from_index` and returning at most `limit` jurors.
+    pub fn get_selected_juror_list(
+        &self,
+        voter_user_id: U128,
+        round: u64,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<JurorApplicationView> {
+        let voter_user_id = voter_user_id.0;
+        let jurysetentries = match self
+            .selected_juror
+            .get(&voter_user_id)
+            .and_then(|jury_rounds| jury_rounds.get(round))
+        {
+            Some(jurysetentries) => jurysetentries,
+            None => return vec![],
+        };
+        let juror_user_ids = jurysetentries.as_vector();
+        (from_index..std::cmp::min(from_index.saturating_add(limit), juror_user_ids.len()))
+            .map(|index| {
+                self.juror_application_view(voter_user_id, juror_user_ids.get(index).unwrap())
+            })
+            .collect()
+    }
+
+    /// Returns the juror applicants of the voter in the order they applied, starting at
+    /// `from_index` and returning at most `limit` applicants.
+    pub fn get_juror_application_list(
+        &self,
+        voter_user_id: U128,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<JurorApplicationView> {
+        let voter_user_id = voter_user_id.0;
+        let juror_applicants = match self.juror_applicants.get(&voter_user_id) {
+            Some(juror_applicants) => juror_applicants,
+            None => return vec![],
+        };
+        (from_index..std::cmp::min(from_index.saturating_add(limit), juror_applicants.len()))
+            .map(|index| {
+                self.juror_application_view(voter_user_id, juror_applicants.get(index).unwrap())
+            })
+            .collect()
+    }
+
+    /// Returns the applications of the juror for every voter the juror applied for, starting
+    /// at `from_index` and returning at most `limit` applications.
+    pub fn get_applications_of_juror(
+        &self,
+        juror_user_id: u128,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<JurorApplicationView> {
+        let applied_for = match self.juror_applied_for.get(&juror_user_id) {
+            Some(applied_for) => applied_for,
+            None => return vec![],
+        };
+        let voter_user_ids = applied_for.as_vector();
+        (from_index..std::cmp::min(from_index.saturating_add(limit), voter_user_ids.len()))
+            .map(|index| {
+                self.juror_application_view(voter_user_ids.get(index).unwrap(), juror_user_id)
+            })
+            .collect()
+    }
+
+    fn juror_application_view(
+        &self,
+        voter_user_id: u128,
+        juror_user_id: u128,
+    ) -> JurorApplicationView {
+        JurorApplicationView {
+            voter_user_id: voter_user_id.into(),
+            voter_account_id: self.get_user_account_id(self.get_applicant_user_id(voter_user_id)),
+            juror_user_id: juror_user_id.into(),
+            juror_account_id: self.get_user_account_id(juror_user_id),
+            application: self
+                .get_juror_application(voter_user_id.into(), juror_user_id)
+                .unwrap(),
+        }
+    }
 }
 
 impl Default for FungibleToken {